@@ -0,0 +1,300 @@
+//! Reading habits computed from retrieved items.
+//!
+//! [`stats`] needs items in every state (`State::All`), retrieved with
+//! `DetailType::Complete` for tags. Weeks start on Monday, in UTC.
+//!
+//! ```
+//! use pockety::analytics::{stats, StatsOptions};
+//!
+//! let stats = stats(&[], &StatsOptions::default().top(5));
+//! assert_eq!(stats.total, 0);
+//! println!("{}", serde_json::to_string_pretty(&stats).unwrap());
+//! ```
+
+use std::collections::{BTreeMap, HashMap};
+
+use chrono::{Datelike, Duration, NaiveTime};
+use serde::{Deserialize, Serialize};
+
+use crate::models::{ItemStatus, PocketItem, Timestamp, DAY, DEFAULT_WORDS_PER_MINUTE};
+
+const WEEK: i64 = 7 * DAY;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StatsOptions {
+    /// How many domains and tags to rank. Defaults to 10.
+    pub top: usize,
+    /// Passed to [`PocketItem::reading_minutes`] for [`ReadingStats::minutes`].
+    /// Defaults to [`DEFAULT_WORDS_PER_MINUTE`].
+    pub words_per_minute: u32,
+    /// The time backlog ages are measured from. Defaults to the current time.
+    pub now: Option<Timestamp>,
+}
+
+impl Default for StatsOptions {
+    fn default() -> Self {
+        Self {
+            top: 10,
+            words_per_minute: DEFAULT_WORDS_PER_MINUTE,
+            now: None,
+        }
+    }
+}
+
+impl StatsOptions {
+    pub fn top(mut self, top: usize) -> Self {
+        self.top = top;
+        self
+    }
+
+    pub fn words_per_minute(mut self, words_per_minute: u32) -> Self {
+        self.words_per_minute = words_per_minute.max(1);
+        self
+    }
+
+    pub fn now(mut self, now: impl Into<Timestamp>) -> Self {
+        self.now = Some(now.into());
+        self
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Stats {
+    /// Items that aren't deleted
+    pub total: usize,
+    pub unread: usize,
+    pub archived: usize,
+    pub favorites: usize,
+    /// Favorites among all items
+    pub favorite_ratio: f64,
+    /// Favorites among archived items
+    pub read_favorite_ratio: f64,
+    /// Every week from the first save or read to the last, oldest first
+    pub weeks: Vec<WeekStats>,
+    pub reading: ReadingStats,
+    pub backlog: BacklogStats,
+    pub top_domains: Vec<Ranked>,
+    pub top_tags: Vec<Ranked>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WeekStats {
+    /// Monday 00:00 UTC
+    pub week_start: Timestamp,
+    /// Items added that week
+    pub saved: usize,
+    /// Items archived that week
+    pub read: usize,
+}
+
+/// How much of the archive was read
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReadingStats {
+    /// Archived items with a `time_read`
+    pub items: usize,
+    /// Words in read items that Pocket has a `word_count` for
+    pub words: u64,
+    /// Estimated reading time, from `time_to_read` or otherwise `word_count`
+    pub minutes: u64,
+}
+
+/// How long unread items have been waiting
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BacklogStats {
+    pub items: usize,
+    pub buckets: Vec<AgeBucket>,
+    pub median_days: Option<u32>,
+    pub oldest_days: Option<u32>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AgeBucket {
+    pub label: String,
+    /// Items at least this many days old...
+    pub min_days: u32,
+    /// ...and younger than this, if bounded
+    pub max_days: Option<u32>,
+    pub count: usize,
+}
+
+/// A domain or tag and how many items have it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Ranked {
+    pub name: String,
+    pub count: usize,
+    pub favorites: usize,
+    pub favorite_ratio: f64,
+}
+
+const AGE_BUCKETS: [(&str, u32, Option<u32>); 5] = [
+    ("under a week", 0, Some(7)),
+    ("1-4 weeks", 7, Some(28)),
+    ("1-3 months", 28, Some(91)),
+    ("3-12 months", 91, Some(365)),
+    ("over a year", 365, None),
+];
+
+pub fn stats<'a>(items: impl IntoIterator<Item = &'a PocketItem>, options: &StatsOptions) -> Stats {
+    let now = options.now.unwrap_or_else(Timestamp::now);
+    let items: Vec<&PocketItem> = items
+        .into_iter()
+        .filter(|item| item.status != ItemStatus::Deleted)
+        .collect();
+
+    let archived: Vec<&PocketItem> = items
+        .iter()
+        .copied()
+        .filter(|item| item.status == ItemStatus::Archived)
+        .collect();
+    let favorites = items.iter().filter(|item| item.is_favorite()).count();
+    let read_favorites = archived.iter().filter(|item| item.is_favorite()).count();
+
+    Stats {
+        total: items.len(),
+        unread: items.len() - archived.len(),
+        archived: archived.len(),
+        favorites,
+        favorite_ratio: ratio(favorites, items.len()),
+        read_favorite_ratio: ratio(read_favorites, archived.len()),
+        weeks: weeks(&items),
+        reading: reading(&archived, options.words_per_minute),
+        backlog: backlog(&items, now),
+        top_domains: ranked(&items, options.top, |item| {
+            item.domain().into_iter().collect()
+        }),
+        top_tags: ranked(&items, options.top, |item| item.tag_names().to_vec()),
+    }
+}
+
+fn weeks(items: &[&PocketItem]) -> Vec<WeekStats> {
+    let mut weeks: BTreeMap<i64, WeekStats> = BTreeMap::new();
+    fn week(weeks: &mut BTreeMap<i64, WeekStats>, time: Timestamp) -> &mut WeekStats {
+        let start = week_start(time);
+        weeks.entry(start.0).or_insert(WeekStats {
+            week_start: start,
+            saved: 0,
+            read: 0,
+        })
+    }
+    for item in items {
+        if let Some(time) = item.time_added {
+            week(&mut weeks, time).saved += 1;
+        }
+        if let Some(time) = item
+            .time_read
+            .filter(|_| item.status == ItemStatus::Archived)
+        {
+            week(&mut weeks, time).read += 1;
+        }
+    }
+
+    // fill in quiet weeks so the series can be charted directly
+    let (Some(&first), Some(&last)) = (weeks.keys().next(), weeks.keys().next_back()) else {
+        return vec![];
+    };
+    (0..=(last - first) / WEEK)
+        .map(|index| first + index * WEEK)
+        .map(|start| {
+            weeks.get(&start).copied().unwrap_or(WeekStats {
+                week_start: Timestamp(start),
+                saved: 0,
+                read: 0,
+            })
+        })
+        .collect()
+}
+
+fn week_start(time: Timestamp) -> Timestamp {
+    let date = time.to_date_time().unwrap_or_default().date_naive();
+    let monday = date - Duration::days(date.weekday().num_days_from_monday().into());
+    Timestamp(monday.and_time(NaiveTime::MIN).and_utc().timestamp())
+}
+
+fn reading(archived: &[&PocketItem], words_per_minute: u32) -> ReadingStats {
+    let mut reading = ReadingStats {
+        items: 0,
+        words: 0,
+        minutes: 0,
+    };
+    for item in archived.iter().filter(|item| item.time_read.is_some()) {
+        reading.items += 1;
+        reading.words += u64::from(item.words().unwrap_or_default());
+        reading.minutes += u64::from(item.reading_minutes(words_per_minute).unwrap_or_default());
+    }
+    reading
+}
+
+fn backlog(items: &[&PocketItem], now: Timestamp) -> BacklogStats {
+    let mut ages: Vec<u32> = items
+        .iter()
+        .filter(|item| item.status == ItemStatus::Normal)
+        .filter_map(|item| item.time_added)
+        .map(|added| u32::try_from((now.0 - added.0).max(0) / DAY).unwrap_or(u32::MAX))
+        .collect();
+    ages.sort_unstable();
+
+    let buckets = AGE_BUCKETS
+        .iter()
+        .map(|&(label, min_days, max_days)| AgeBucket {
+            label: label.to_string(),
+            min_days,
+            max_days,
+            count: ages
+                .iter()
+                .filter(|&&age| age >= min_days && max_days.is_none_or(|max| age < max))
+                .count(),
+        })
+        .collect();
+
+    BacklogStats {
+        items: ages.len(),
+        buckets,
+        median_days: median(&ages),
+        oldest_days: ages.last().copied(),
+    }
+}
+
+fn ranked(
+    items: &[&PocketItem],
+    top: usize,
+    keys: impl Fn(&PocketItem) -> Vec<String>,
+) -> Vec<Ranked> {
+    let mut counts: HashMap<String, (usize, usize)> = HashMap::new();
+    for item in items {
+        for key in keys(item) {
+            let (count, favorites) = counts.entry(key).or_default();
+            *count += 1;
+            *favorites += usize::from(item.is_favorite());
+        }
+    }
+
+    let mut ranked: Vec<Ranked> = counts
+        .into_iter()
+        .map(|(name, (count, favorites))| Ranked {
+            name,
+            count,
+            favorites,
+            favorite_ratio: ratio(favorites, count),
+        })
+        .collect();
+    ranked.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.name.cmp(&b.name)));
+    ranked.truncate(top);
+    ranked
+}
+
+fn median(sorted: &[u32]) -> Option<u32> {
+    let middle = sorted.len() / 2;
+    match sorted.len() {
+        0 => None,
+        len if len % 2 == 1 => Some(sorted[middle]),
+        _ => Some(((u64::from(sorted[middle - 1]) + u64::from(sorted[middle])) / 2) as u32),
+    }
+}
+
+fn ratio(part: usize, whole: usize) -> f64 {
+    if whole == 0 {
+        0.0
+    } else {
+        part as f64 / whole as f64
+    }
+}
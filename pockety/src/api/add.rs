@@ -1,7 +1,12 @@
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    models::{ItemHas, ItemId, ItemImage, ItemVideo, Tags, Timestamp},
+    models::{
+        deserialize_date_time, deserialize_extra, deserialize_map_values,
+        deserialize_optional_bool, deserialize_optional_u32, serialize_comma_separated, ItemAuthor,
+        ItemHas, ItemId, ItemImage, ItemVideo, Tags, Timestamp,
+    },
+    url::{normalize_url, NormalizeOptions},
     ApiResult, Pockety,
 };
 
@@ -10,68 +15,120 @@ pub struct AddRequestBody {
     pub consumer_key: String,
     pub access_token: String,
     pub url: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub title: Option<String>,
+    #[serde(
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "serialize_comma_separated"
+    )]
     pub tags: Option<Tags>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub tweet_id: Option<String>,
 }
 
-#[derive(Serialize, Deserialize, Debug, PartialEq)]
+/// The response of `/v3/add`
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
 pub struct AddResponse {
+    /// The item that was added
+    pub item: AddedItem,
+    /// 1 on success
+    pub status: u16,
+    /// Any fields Pocket sends that aren't modelled above
+    #[serde(flatten, deserialize_with = "deserialize_extra")]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+/// An item as Pocket returns it after adding it, either from `/v3/add` or
+/// from an `add` action on `/v3/send`. Pocket may not have parsed the page
+/// yet, so everything but the `item_id` is optional.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct AddedItem {
     /// A unique identifier for the added item
     pub item_id: ItemId,
     /// The original url for the added item
-    pub normal_url: String,
+    pub normal_url: Option<String>,
+    /// The url as it was given to Pocket
+    pub given_url: Option<String>,
     /// A unique identifier for the resolved item
-    pub resolved_id: ItemId,
+    pub resolved_id: Option<ItemId>,
+    pub extended_item_id: Option<ItemId>,
     /// The resolved url for the added item. The easiest way to think about the
     /// resolved_url - if you add a bit.ly link, the resolved_url will be the
     /// url of the page the bit.ly link points to
-    pub resolved_url: String,
+    pub resolved_url: Option<String>,
+    /// The normalized form of resolved_url
+    pub resolved_normal_url: Option<String>,
     /// A unique identifier for the domain of the resolved_url
-    pub domain_id: ItemId,
+    pub domain_id: Option<ItemId>,
     /// A unique identifier for the domain of the normal_url
-    pub origin_domain_id: ItemId,
+    pub origin_domain_id: Option<ItemId>,
     /// The response code received by the Pocket parser when it tried to access
     /// the item
-    pub response_code: String,
+    pub response_code: Option<String>,
     ///  The MIME type returned by the item
-    pub mime_type: String,
+    pub mime_type: Option<String>,
     /// The content length of the item
-    pub content_length: u32,
+    #[serde(default, deserialize_with = "deserialize_optional_u32")]
+    pub content_length: Option<u32>,
     /// The encoding of the item
-    pub encoding: String,
+    pub encoding: Option<String>,
+    /// The language of the item
+    pub lang: Option<String>,
     /// The date the item was resolved
-    pub date_resolved: Timestamp,
+    #[serde(default, deserialize_with = "deserialize_date_time")]
+    pub date_resolved: Option<Timestamp>,
     /// The date the item was published (if the parser was able to find one)
-    pub date_published: Timestamp,
+    #[serde(default, deserialize_with = "deserialize_date_time")]
+    pub date_published: Option<Timestamp>,
     /// The title of the resolved_url
-    pub title: String,
+    pub title: Option<String>,
     /// The excerpt of the resolved_url
-    pub excerpt: String,
+    pub excerpt: Option<String>,
     /// For an article, the number of words
-    pub word_count: u32,
+    #[serde(default, deserialize_with = "deserialize_optional_u32")]
+    pub word_count: Option<u32>,
     /// 0: no image; 1: has an image in the body of the article; 2: is an image
-    pub has_image: ItemHas,
+    pub has_image: Option<ItemHas>,
     /// 0: no video; 1: has a video in the body of the article; 2: is a video
-    pub has_video: ItemHas,
+    pub has_video: Option<ItemHas>,
     /// 0 or 1; If the parser thinks this item is an index page it will be set
     /// to 1
-    pub is_index: bool,
+    #[serde(default, deserialize_with = "deserialize_optional_bool")]
+    pub is_index: Option<bool>,
     /// 0 or 1; If the parser thinks this item is an article it will be set to 1
-    pub is_article: bool,
-    /// Array of author data (if author(s) were found)
-    pub authors: Vec<String>,
-    // TODO: Should be ItemAuthor
-    /// Array of image data (if image(s) were found)
-    pub images: Vec<ItemImage>,
-    /// Array of video data (if video(s) were found)
-    pub videos: Vec<ItemVideo>,
+    #[serde(default, deserialize_with = "deserialize_optional_bool")]
+    pub is_article: Option<bool>,
+    /// 0 or 1; Whether the url redirected within the same domain
+    #[serde(default, deserialize_with = "deserialize_optional_bool")]
+    pub innerdomain_redirect: Option<bool>,
+    /// 0 or 1; Whether the page required a login to parse
+    #[serde(default, deserialize_with = "deserialize_optional_bool")]
+    pub login_required: Option<bool>,
+    /// 0 or 1; Whether the parser fell back to a simpler parsing strategy
+    #[serde(default, deserialize_with = "deserialize_optional_bool")]
+    pub used_fallback: Option<bool>,
+    /// When Pocket first parsed the url
+    #[serde(default, deserialize_with = "deserialize_date_time")]
+    pub time_first_parsed: Option<Timestamp>,
+    /// Author data (if author(s) were found)
+    #[serde(default, deserialize_with = "deserialize_map_values")]
+    pub authors: Option<Vec<ItemAuthor>>,
+    /// Image data (if image(s) were found)
+    #[serde(default, deserialize_with = "deserialize_map_values")]
+    pub images: Option<Vec<ItemImage>>,
+    /// Video data (if video(s) were found)
+    #[serde(default, deserialize_with = "deserialize_map_values")]
+    pub videos: Option<Vec<ItemVideo>>,
+    /// Any fields Pocket sends that aren't modelled above
+    #[serde(flatten, deserialize_with = "deserialize_extra")]
+    pub extra: serde_json::Map<String, serde_json::Value>,
 }
 
 #[derive(Debug)]
 pub struct AddHandler<'po> {
     pockety: &'po Pockety,
     body: AddRequestBody,
+    normalize: Option<NormalizeOptions>,
 }
 
 impl<'po> AddHandler<'po> {
@@ -79,6 +136,7 @@ impl<'po> AddHandler<'po> {
         Self {
             pockety,
             body: Default::default(),
+            normalize: None,
         }
     }
 
@@ -87,8 +145,15 @@ impl<'po> AddHandler<'po> {
         self
     }
 
-    pub fn url(mut self, url: String) -> Self {
-        self.body.url = url;
+    pub fn url(mut self, url: impl Into<String>) -> Self {
+        self.body.url = url.into();
+        self
+    }
+
+    /// Validate and normalize the url with [`normalize_url`] before sending
+    /// it; an invalid url fails with [`Error::Url`](crate::Error::Url) without making a request.
+    pub fn normalize_url(mut self, options: NormalizeOptions) -> Self {
+        self.normalize = Some(options);
         self
     }
 
@@ -108,13 +173,18 @@ impl<'po> AddHandler<'po> {
     }
 
     pub async fn send(self) -> ApiResult<AddResponse> {
+        let url = match &self.normalize {
+            Some(options) => normalize_url(&self.body.url, options)?,
+            None => self.body.url,
+        };
         let body = AddRequestBody {
             consumer_key: self.pockety.consumer_key.clone(),
+            url,
             ..self.body
         };
 
         self.pockety
-            .post::<AddRequestBody, AddResponse>("/send", Some(&body))
+            .post::<AddRequestBody, AddResponse>("/add", Some(&body))
             .await
     }
 }
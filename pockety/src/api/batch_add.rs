@@ -0,0 +1,208 @@
+use crate::{
+    api::{
+        add::AddedItem,
+        modify::{
+            ActionError, ActionOutcome, ActionResult, Add, PartialSend, PocketAction,
+            DEFAULT_CHUNK_SIZE,
+        },
+    },
+    models::{Tags, Timestamp},
+    ApiResult, Error, Pockety, PocketyResponse,
+};
+
+/// A url to add, with the details Pocket should save along with it
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct NewItem {
+    pub url: String,
+    pub title: Option<String>,
+    pub tags: Option<Tags>,
+    /// When the item was saved, e.g. when importing from another service
+    pub time: Option<Timestamp>,
+}
+
+impl NewItem {
+    pub fn new(url: impl Into<String>) -> Self {
+        Self {
+            url: url.into(),
+            ..Default::default()
+        }
+    }
+
+    pub fn title(mut self, title: impl Into<String>) -> Self {
+        self.title = Some(title.into());
+        self
+    }
+
+    pub fn tags(mut self, tags: impl Into<Tags>) -> Self {
+        self.tags = Some(tags.into());
+        self
+    }
+
+    pub fn time(mut self, time: impl Into<Timestamp>) -> Self {
+        self.time = Some(time.into());
+        self
+    }
+}
+
+impl From<NewItem> for PocketAction {
+    fn from(item: NewItem) -> Self {
+        PocketAction::Add(Add {
+            item_id: None,
+            ref_id: None,
+            tags: item
+                .tags
+                .filter(|tags| !tags.0.is_empty())
+                .map(|tags| tags.0.join(",")),
+            time: item.time,
+            title: item.title,
+            url: Some(item.url),
+        })
+    }
+}
+
+impl NewItem {
+    /// The item an `add` action was made from
+    fn from_action(action: PocketAction) -> Option<Self> {
+        let PocketAction::Add(add) = action else {
+            return None;
+        };
+        Some(Self {
+            url: add.url?,
+            title: add.title,
+            tags: add.tags.map(|tags| tags.split(',').collect()),
+            time: add.time,
+        })
+    }
+}
+
+/// How Pocket handled one of the urls of a [`BatchAddHandler`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct BatchAddResult {
+    pub url: String,
+    pub success: bool,
+    pub error: Option<ActionError>,
+    /// The added item, if Pocket returned it
+    pub item: Option<AddedItem>,
+}
+
+impl From<ActionOutcome> for BatchAddResult {
+    fn from(outcome: ActionOutcome) -> Self {
+        Self {
+            url: match outcome.action {
+                PocketAction::Add(add) => add.url.unwrap_or_default(),
+                _ => String::new(),
+            },
+            success: outcome.result.is_success(),
+            error: outcome.error,
+            item: match outcome.result {
+                ActionResult::Item(item) => serde_json::from_value(item).ok(),
+                ActionResult::Success(_) => None,
+            },
+        }
+    }
+}
+
+/// How far a [`BatchAddHandler`] got before a chunk failed
+#[derive(Debug)]
+pub struct PartialAdd {
+    /// The urls of the chunks Pocket applied
+    pub added: Vec<BatchAddResult>,
+    /// The items of the chunks that failed or weren't sent, in order
+    pub unsent: Vec<NewItem>,
+    /// Why the first chunk failed
+    pub error: Box<Error>,
+}
+
+/// Adds many urls in one go through `add` actions on `/v3/send`, rather than
+/// one `/v3/add` request per url.
+#[derive(Debug)]
+pub struct BatchAddHandler<'po> {
+    pockety: &'po Pockety,
+    access_token: String,
+    items: Vec<NewItem>,
+    chunk_size: usize,
+    concurrency: usize,
+}
+
+impl<'po> BatchAddHandler<'po> {
+    pub fn new(pockety: &'po Pockety) -> Self {
+        Self {
+            pockety,
+            access_token: Default::default(),
+            items: Default::default(),
+            chunk_size: DEFAULT_CHUNK_SIZE,
+            concurrency: 1,
+        }
+    }
+
+    pub fn access_token(mut self, access_token: String) -> Self {
+        self.access_token = access_token;
+        self
+    }
+
+    pub fn url(self, url: impl Into<String>) -> Self {
+        self.push(NewItem::new(url))
+    }
+
+    pub fn push(mut self, item: NewItem) -> Self {
+        self.items.push(item);
+        self
+    }
+
+    pub fn extend(mut self, items: impl IntoIterator<Item = NewItem>) -> Self {
+        self.items.extend(items);
+        self
+    }
+
+    /// See [`ModifyHandler::chunk_size`](crate::api::modify::ModifyHandler::chunk_size).
+    /// Defaults to [`DEFAULT_CHUNK_SIZE`].
+    pub fn chunk_size(mut self, chunk_size: usize) -> Self {
+        self.chunk_size = chunk_size.max(1);
+        self
+    }
+
+    /// See [`ModifyHandler::concurrency`](crate::api::modify::ModifyHandler::concurrency)
+    pub fn concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    /// Returns one result per url, in the order they were pushed. If a chunk
+    /// fails after Pocket applied others, the error is
+    /// [`Error::PartiallyAdded`] with the results of the applied ones.
+    pub async fn send(self) -> ApiResult<Vec<BatchAddResult>> {
+        let response = self
+            .pockety
+            .modify()
+            .access_token(self.access_token)
+            .chunk_size(self.chunk_size)
+            .concurrency(self.concurrency)
+            .extend(self.items.into_iter().map(PocketAction::from))
+            .send()
+            .await
+            .map_err(|error| match error {
+                Error::PartiallySent(PartialSend {
+                    outcome,
+                    unsent,
+                    error,
+                }) => Error::PartiallyAdded(PartialAdd {
+                    added: outcome.into_iter().map(BatchAddResult::from).collect(),
+                    unsent: unsent
+                        .into_iter()
+                        .filter_map(NewItem::from_action)
+                        .collect(),
+                    error,
+                }),
+                error => error,
+            })?;
+
+        Ok(PocketyResponse {
+            rate_limits: response.rate_limits,
+            data: response
+                .data
+                .into_iter()
+                .map(BatchAddResult::from)
+                .collect(),
+        })
+    }
+}
@@ -0,0 +1,229 @@
+use crate::{
+    api::modify::{ModifyOutcome, PocketAction, DEFAULT_CHUNK_SIZE},
+    models::{ItemId, ItemStatus, PocketItem, Tags},
+    query::Query,
+    ApiResult, Pockety, PocketyResponse,
+};
+
+/// What to do with every item matched by a [`BulkHandler`]'s query
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BulkAction {
+    Archive,
+    Readd,
+    Favorite,
+    Unfavorite,
+    Delete,
+    TagsAdd(Tags),
+    TagsRemove(Tags),
+    TagsReplace(Tags),
+    TagsClear,
+}
+
+impl BulkAction {
+    /// The action for a single item, or `None` if it wouldn't change anything
+    pub fn for_item(&self, item: &PocketItem) -> Option<PocketAction> {
+        let item_id = item.item_id.clone();
+        let action = match self {
+            BulkAction::Archive if item.status == ItemStatus::Archived => return None,
+            BulkAction::Archive => PocketAction::archive(item_id),
+            BulkAction::Readd if item.status == ItemStatus::Normal => return None,
+            BulkAction::Readd => PocketAction::readd(item_id),
+            BulkAction::Favorite if item.is_favorite() => return None,
+            BulkAction::Favorite => PocketAction::favorite(item_id),
+            BulkAction::Unfavorite if !item.is_favorite() => return None,
+            BulkAction::Unfavorite => PocketAction::unfavorite(item_id),
+            BulkAction::Delete => PocketAction::delete(item_id),
+            BulkAction::TagsAdd(tags) => PocketAction::tags_add(item_id, tags.clone()),
+            BulkAction::TagsRemove(tags) => PocketAction::tags_remove(item_id, tags.clone()),
+            BulkAction::TagsReplace(tags) => PocketAction::tags_replace(item_id, tags.clone()),
+            BulkAction::TagsClear => PocketAction::tags_clear(item_id),
+        };
+
+        Some(action)
+    }
+}
+
+#[derive(Debug)]
+pub enum BulkOutcome {
+    /// The actions that would have been sent in a dry run
+    Planned(Vec<PocketAction>),
+    /// The actions that were sent and how Pocket handled them
+    Sent(ModifyOutcome),
+}
+
+impl BulkOutcome {
+    pub fn actions(&self) -> Vec<&PocketAction> {
+        match self {
+            BulkOutcome::Planned(actions) => actions.iter().collect(),
+            BulkOutcome::Sent(outcome) => outcome.iter().map(|outcome| &outcome.action).collect(),
+        }
+    }
+
+    /// The ids of the items the actions apply to, without duplicates
+    pub fn item_ids(&self) -> Vec<&ItemId> {
+        let mut item_ids: Vec<&ItemId> = Vec::new();
+        for item_id in self.actions().into_iter().filter_map(PocketAction::item_id) {
+            if !item_ids.contains(&item_id) {
+                item_ids.push(item_id);
+            }
+        }
+        item_ids
+    }
+}
+
+/// Applies actions to every item matching a [`Query`]: pages through
+/// `/v3/get`, builds one action per item and matching [`BulkAction`], and
+/// sends them through a chunked `/v3/send`.
+///
+/// ```no_run
+/// # async fn run(pockety: pockety::Pockety, access_token: String) -> Result<(), pockety::Error> {
+/// use chrono::{Duration, Utc};
+/// use pockety::{models::State, query::Query};
+///
+/// let outcome = pockety
+///     .bulk()
+///     .access_token(access_token)
+///     .query(
+///         Query::new()
+///             .state(State::Unread)
+///             .added_before(Utc::now() - Duration::days(90)),
+///     )
+///     .archive()
+///     .send()
+///     .await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct BulkHandler<'po> {
+    pockety: &'po Pockety,
+    access_token: String,
+    query: Query,
+    actions: Vec<BulkAction>,
+    dry_run: bool,
+    chunk_size: usize,
+    concurrency: usize,
+}
+
+impl<'po> BulkHandler<'po> {
+    pub fn new(pockety: &'po Pockety) -> Self {
+        Self {
+            pockety,
+            access_token: Default::default(),
+            query: Default::default(),
+            actions: Default::default(),
+            dry_run: false,
+            chunk_size: DEFAULT_CHUNK_SIZE,
+            concurrency: 1,
+        }
+    }
+
+    pub fn access_token(mut self, access_token: String) -> Self {
+        self.access_token = access_token;
+        self
+    }
+
+    pub fn query(mut self, query: Query) -> Self {
+        self.query = query;
+        self
+    }
+
+    /// Only plan the actions, don't send them
+    pub fn dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+
+    pub fn chunk_size(mut self, chunk_size: usize) -> Self {
+        self.chunk_size = chunk_size.max(1);
+        self
+    }
+
+    pub fn concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    pub fn action(mut self, action: BulkAction) -> Self {
+        self.actions.push(action);
+        self
+    }
+
+    pub fn archive(self) -> Self {
+        self.action(BulkAction::Archive)
+    }
+
+    pub fn readd(self) -> Self {
+        self.action(BulkAction::Readd)
+    }
+
+    pub fn favorite(self) -> Self {
+        self.action(BulkAction::Favorite)
+    }
+
+    pub fn unfavorite(self) -> Self {
+        self.action(BulkAction::Unfavorite)
+    }
+
+    pub fn delete(self) -> Self {
+        self.action(BulkAction::Delete)
+    }
+
+    pub fn tags_add(self, tags: impl Into<Tags>) -> Self {
+        self.action(BulkAction::TagsAdd(tags.into()))
+    }
+
+    pub fn tags_remove(self, tags: impl Into<Tags>) -> Self {
+        self.action(BulkAction::TagsRemove(tags.into()))
+    }
+
+    pub fn tags_replace(self, tags: impl Into<Tags>) -> Self {
+        self.action(BulkAction::TagsReplace(tags.into()))
+    }
+
+    pub fn tags_clear(self) -> Self {
+        self.action(BulkAction::TagsClear)
+    }
+
+    /// The actions for the given items, in item order
+    pub fn plan<'a>(&self, items: impl IntoIterator<Item = &'a PocketItem>) -> Vec<PocketAction> {
+        items
+            .into_iter()
+            .flat_map(|item| {
+                self.actions
+                    .iter()
+                    .filter_map(move |action| action.for_item(item))
+            })
+            .collect()
+    }
+
+    pub async fn send(self) -> ApiResult<BulkOutcome> {
+        let items = self
+            .pockety
+            .retrieve()
+            .access_token(self.access_token.clone())
+            .execute_query(&self.query)
+            .await?;
+        let actions = self.plan(&items.data);
+
+        if self.dry_run {
+            return Ok(PocketyResponse {
+                rate_limits: items.rate_limits,
+                data: BulkOutcome::Planned(actions),
+            });
+        }
+
+        self.pockety
+            .modify()
+            .access_token(self.access_token)
+            .chunk_size(self.chunk_size)
+            .concurrency(self.concurrency)
+            .extend(actions)
+            .send()
+            .await
+            .map(|res| PocketyResponse {
+                rate_limits: res.rate_limits,
+                data: BulkOutcome::Sent(res.data),
+            })
+    }
+}
@@ -0,0 +1,219 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::{
+    api::{
+        batch_add::{BatchAddResult, NewItem},
+        modify::{ModifyOutcome, PocketAction, DEFAULT_CHUNK_SIZE},
+    },
+    dedup::url_key,
+    import::ImportedItem,
+    models::State,
+    query::Query,
+    ApiResult, Pockety, PocketyResponse,
+};
+
+/// What an [`ImportHandler`] did with each imported item
+#[derive(Debug, Clone)]
+pub struct ImportReport {
+    /// How Pocket handled each item that was added
+    pub added: Vec<BatchAddResult>,
+    /// Items that weren't added because they are already in the list
+    pub existing: Vec<ImportedItem>,
+    /// Items that weren't added because the import has them more than once.
+    /// The first copy is added with the tags of every copy, and is favorited
+    /// if any copy is and archived only if every copy is.
+    pub duplicates: Vec<ImportedItem>,
+    /// The `archive` and `favorite` actions sent for added items
+    pub status: ModifyOutcome,
+    /// Items that were added but are archived or favorites in the export,
+    /// and whose status wasn't restored because Pocket's response didn't
+    /// include their item id
+    pub status_unrestored: Vec<ImportedItem>,
+}
+
+/// Adds [`ImportedItem`]s to a user's list through chunked `add` actions on
+/// `/v3/send`, keeping their original save time and tags, then archives and
+/// favorites the ones that were archived or favorited in the export.
+///
+/// Urls are compared after normalization (see
+/// [`dedup`](crate::dedup)), so items already in the list, or in the import
+/// more than once, are only added once.
+///
+/// ```no_run
+/// # async fn run(pockety: pockety::Pockety, access_token: String) -> Result<(), pockety::Error> {
+/// use pockety::import::{parse, ImportFormat};
+///
+/// let export = std::fs::read_to_string("ril_export.html")?;
+/// let report = pockety
+///     .import()
+///     .access_token(access_token)
+///     .extend(parse(&export, ImportFormat::Html)?)
+///     .send()
+///     .await?;
+/// println!("added {} items", report.data.added.len());
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct ImportHandler<'po> {
+    pockety: &'po Pockety,
+    access_token: String,
+    items: Vec<ImportedItem>,
+    skip_existing: bool,
+    chunk_size: usize,
+    concurrency: usize,
+}
+
+impl<'po> ImportHandler<'po> {
+    pub fn new(pockety: &'po Pockety) -> Self {
+        Self {
+            pockety,
+            access_token: Default::default(),
+            items: Default::default(),
+            skip_existing: true,
+            chunk_size: DEFAULT_CHUNK_SIZE,
+            concurrency: 1,
+        }
+    }
+
+    pub fn access_token(mut self, access_token: String) -> Self {
+        self.access_token = access_token;
+        self
+    }
+
+    pub fn push(mut self, item: ImportedItem) -> Self {
+        self.items.push(item);
+        self
+    }
+
+    pub fn extend(mut self, items: impl IntoIterator<Item = ImportedItem>) -> Self {
+        self.items.extend(items);
+        self
+    }
+
+    /// Whether to retrieve the user's list first and skip items already in
+    /// it. Defaults to `true`.
+    pub fn skip_existing(mut self, skip_existing: bool) -> Self {
+        self.skip_existing = skip_existing;
+        self
+    }
+
+    pub fn chunk_size(mut self, chunk_size: usize) -> Self {
+        self.chunk_size = chunk_size.max(1);
+        self
+    }
+
+    pub fn concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    pub async fn send(self) -> ApiResult<ImportReport> {
+        let (items, duplicates) = merge_duplicates(self.items);
+
+        let (items, existing) = if self.skip_existing {
+            let list = self
+                .pockety
+                .retrieve()
+                .access_token(self.access_token.clone())
+                .execute_query(&Query::new().state(State::All))
+                .await?;
+            let saved: HashSet<String> = list
+                .data
+                .iter()
+                .flat_map(|item| [item.given_url.as_deref(), item.resolved_url.as_deref()])
+                .flatten()
+                .filter_map(url_key)
+                .collect();
+            items
+                .into_iter()
+                .partition(|item| url_key(&item.url).is_none_or(|key| !saved.contains(&key)))
+        } else {
+            (items, vec![])
+        };
+
+        let added = self
+            .pockety
+            .batch_add()
+            .access_token(self.access_token.clone())
+            .chunk_size(self.chunk_size)
+            .concurrency(self.concurrency)
+            .extend(items.iter().cloned().map(NewItem::from))
+            .send()
+            .await?;
+
+        let mut actions = Vec::new();
+        let mut status_unrestored = Vec::new();
+        for (item, result) in items.iter().zip(&added.data) {
+            if !result.success || !(item.archived || item.favorite) {
+                continue;
+            }
+            let Some(added) = &result.item else {
+                status_unrestored.push(item.clone());
+                continue;
+            };
+            if item.archived {
+                actions.push(PocketAction::archive(&added.item_id));
+            }
+            if item.favorite {
+                actions.push(PocketAction::favorite(&added.item_id));
+            }
+        }
+
+        let (rate_limits, status) = if actions.is_empty() {
+            (added.rate_limits, ModifyOutcome::default())
+        } else {
+            let response = self
+                .pockety
+                .modify()
+                .access_token(self.access_token)
+                .chunk_size(self.chunk_size)
+                .concurrency(self.concurrency)
+                .extend(actions)
+                .send()
+                .await?;
+            (response.rate_limits, response.data)
+        };
+
+        Ok(PocketyResponse {
+            rate_limits,
+            data: ImportReport {
+                added: added.data,
+                existing,
+                duplicates,
+                status,
+                status_unrestored,
+            },
+        })
+    }
+}
+
+/// Keeps the first item per normalized url, with the tags of every copy
+fn merge_duplicates(items: Vec<ImportedItem>) -> (Vec<ImportedItem>, Vec<ImportedItem>) {
+    let mut unique: Vec<ImportedItem> = Vec::new();
+    let mut duplicates = Vec::new();
+    let mut seen: HashMap<String, usize> = HashMap::new();
+
+    for item in items {
+        let key = url_key(&item.url).unwrap_or_else(|| item.url.clone());
+        match seen.get(&key) {
+            Some(&index) => {
+                let first = &mut unique[index];
+                for tag in &item.tags {
+                    if !first.tags.contains(tag) {
+                        first.tags.push(tag.clone());
+                    }
+                }
+                first.archived &= item.archived;
+                first.favorite |= item.favorite;
+                duplicates.push(item);
+            }
+            None => {
+                seen.insert(key, unique.len());
+                unique.push(item);
+            }
+        }
+    }
+
+    (unique, duplicates)
+}
@@ -0,0 +1,165 @@
+use crate::{
+    api::modify::PocketAction,
+    models::{ItemStatus, PocketItem, Tags, Timestamp},
+    ApiResult, Pockety, PocketyResponse,
+};
+
+/// A retrieved item along with what's needed to act on it, so that e.g.
+/// `item.archive().await` can be used rather than building a
+/// [`ModifyHandler`](crate::api::modify::ModifyHandler) by hand.
+///
+/// Every action is sent on its own and, once Pocket has applied it, also
+/// applied to the local copy of the item, which is returned. If Pocket
+/// rejects the action an [`Error::ActionsFailed`](crate::Error::ActionsFailed)
+/// is returned and the local copy is left untouched.
+#[derive(Debug, Clone)]
+pub struct ItemHandle<'po> {
+    pockety: &'po Pockety,
+    access_token: String,
+    item: PocketItem,
+}
+
+impl<'po> ItemHandle<'po> {
+    pub fn new(pockety: &'po Pockety, access_token: impl Into<String>, item: PocketItem) -> Self {
+        Self {
+            pockety,
+            access_token: access_token.into(),
+            item,
+        }
+    }
+
+    /// The local copy of the item
+    pub fn item(&self) -> &PocketItem {
+        &self.item
+    }
+
+    pub fn into_item(self) -> PocketItem {
+        self.item
+    }
+
+    pub async fn archive(&mut self) -> ApiResult<PocketItem> {
+        let action = PocketAction::archive(&self.item.item_id);
+        self.send(action, |item, now| {
+            item.status = ItemStatus::Archived;
+            item.time_read = Some(now);
+        })
+        .await
+    }
+
+    pub async fn readd(&mut self) -> ApiResult<PocketItem> {
+        let action = PocketAction::readd(&self.item.item_id);
+        self.send(action, |item, _| {
+            item.status = ItemStatus::Normal;
+            item.time_read = None;
+        })
+        .await
+    }
+
+    pub async fn favorite(&mut self) -> ApiResult<PocketItem> {
+        let action = PocketAction::favorite(&self.item.item_id);
+        self.send(action, |item, now| {
+            item.favorite = Some("1".to_string());
+            item.time_favorited = Some(now);
+        })
+        .await
+    }
+
+    pub async fn unfavorite(&mut self) -> ApiResult<PocketItem> {
+        let action = PocketAction::unfavorite(&self.item.item_id);
+        self.send(action, |item, _| {
+            item.favorite = Some("0".to_string());
+            item.time_favorited = None;
+        })
+        .await
+    }
+
+    /// Permanently removes the item. The returned copy is marked
+    /// [`ItemStatus::Deleted`].
+    pub async fn delete(&mut self) -> ApiResult<PocketItem> {
+        let action = PocketAction::delete(&self.item.item_id);
+        self.send(action, |item, _| item.status = ItemStatus::Deleted)
+            .await
+    }
+
+    /// Operations on the item's tags. The local copy only knows the item's
+    /// tags if it was retrieved with `DetailType::Complete`.
+    pub fn tags(&mut self) -> ItemTags<'_, 'po> {
+        ItemTags { handle: self }
+    }
+
+    async fn send(
+        &mut self,
+        action: PocketAction,
+        apply: impl FnOnce(&mut PocketItem, Timestamp),
+    ) -> ApiResult<PocketItem> {
+        let response = self
+            .pockety
+            .modify()
+            .access_token(self.access_token.clone())
+            .fail_on_action_errors(true)
+            .push(action)
+            .send()
+            .await?;
+
+        let now = Timestamp::now();
+        apply(&mut self.item, now);
+        self.item.time_updated = Some(now);
+
+        Ok(PocketyResponse {
+            rate_limits: response.rate_limits,
+            data: self.item.clone(),
+        })
+    }
+}
+
+/// Tag operations on an [`ItemHandle`], see [`ItemHandle::tags`]
+#[derive(Debug)]
+pub struct ItemTags<'h, 'po> {
+    handle: &'h mut ItemHandle<'po>,
+}
+
+impl ItemTags<'_, '_> {
+    pub async fn add(self, tags: impl Into<Tags>) -> ApiResult<PocketItem> {
+        let tags = tags.into();
+        let action = PocketAction::tags_add(&self.handle.item.item_id, tags.clone());
+        self.handle
+            .send(action, |item, _| {
+                let current = item.tags.get_or_insert_with(|| Tags(Vec::new()));
+                for tag in tags.0 {
+                    if !current.contains(&tag) {
+                        current.0.push(tag);
+                    }
+                }
+            })
+            .await
+    }
+
+    pub async fn remove(self, tags: impl Into<Tags>) -> ApiResult<PocketItem> {
+        let tags = tags.into();
+        let action = PocketAction::tags_remove(&self.handle.item.item_id, tags.clone());
+        self.handle
+            .send(action, |item, _| {
+                if let Some(current) = &mut item.tags {
+                    current.0.retain(|tag| !tags.contains(tag));
+                }
+            })
+            .await
+    }
+
+    /// Replaces all of the item's tags
+    pub async fn replace(self, tags: impl Into<Tags>) -> ApiResult<PocketItem> {
+        let tags = tags.into();
+        let action = PocketAction::tags_replace(&self.handle.item.item_id, tags.clone());
+        self.handle
+            .send(action, |item, _| item.tags = Some(tags))
+            .await
+    }
+
+    /// Removes all of the item's tags
+    pub async fn clear(self) -> ApiResult<PocketItem> {
+        let action = PocketAction::tags_clear(&self.handle.item.item_id);
+        self.handle
+            .send(action, |item, _| item.tags = Some(Tags(Vec::new())))
+            .await
+    }
+}
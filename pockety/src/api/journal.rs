@@ -0,0 +1,266 @@
+use std::{
+    fs::File,
+    io::{BufReader, BufWriter},
+    path::Path,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    api::modify::{Add, PocketAction},
+    models::{ItemId, ItemStatus, PocketItem, Tags, Timestamp},
+    Error,
+};
+
+/// The state of an item before a batch of actions was applied to it
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ItemSnapshot {
+    pub item_id: ItemId,
+    pub status: ItemStatus,
+    pub favorite: bool,
+    pub tags: Tags,
+    pub url: Option<String>,
+    pub title: Option<String>,
+    pub time_added: Option<Timestamp>,
+}
+
+impl From<&PocketItem> for ItemSnapshot {
+    fn from(item: &PocketItem) -> Self {
+        Self {
+            item_id: item.item_id.clone(),
+            status: item.status,
+            favorite: item.is_favorite(),
+            tags: Tags(item.tag_names().to_vec()),
+            url: item.given_url.clone(),
+            title: item.title().map(str::to_string),
+            time_added: item.time_added,
+        }
+    }
+}
+
+/// A record of a modify batch that can be replayed to undo it.
+///
+/// Undoing restores the status, favorite flag and tags of every item the
+/// batch touched. Deleted items are added back by url with their title, tags
+/// and save time, then favorited and archived as they were. Items that weren't
+/// in the user's list when the journal was recorded can't be restored, so
+/// `add` actions aren't undone: their item ids are only known once Pocket has
+/// handled them.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Journal {
+    pub created: Timestamp,
+    /// The batch the journal was recorded for
+    pub actions: Vec<PocketAction>,
+    /// The state of every item the batch touches, before it was sent
+    pub snapshots: Vec<ItemSnapshot>,
+    /// Actions that restore `snapshots`
+    pub inverse: Vec<PocketAction>,
+}
+
+#[derive(Debug, Default)]
+struct Touched {
+    status: bool,
+    favorite: bool,
+    tags: bool,
+    deleted: bool,
+}
+
+impl Journal {
+    /// Records `actions` against `items`, the user's list before the actions
+    /// are sent. Items need their tags, i.e. be retrieved with
+    /// `DetailType::Complete`.
+    pub fn record(actions: Vec<PocketAction>, items: &[PocketItem]) -> Self {
+        let mut touched: Vec<(&PocketItem, Touched)> = Vec::new();
+
+        for action in &actions {
+            let affected: Vec<&PocketItem> = match action {
+                PocketAction::TagRename(rename) => items
+                    .iter()
+                    .filter(|item| item.has_tag(&rename.old_tag))
+                    .collect(),
+                PocketAction::TagDelete(delete) => items
+                    .iter()
+                    .filter(|item| item.has_tag(&delete.tag))
+                    .collect(),
+                action => items
+                    .iter()
+                    .filter(|item| Some(&item.item_id) == action.item_id())
+                    .collect(),
+            };
+
+            for item in affected {
+                let index = match touched
+                    .iter()
+                    .position(|(other, _)| other.item_id == item.item_id)
+                {
+                    Some(index) => index,
+                    None => {
+                        touched.push((item, Touched::default()));
+                        touched.len() - 1
+                    }
+                };
+                let touched = &mut touched[index].1;
+                match action {
+                    PocketAction::Add(_) => {}
+                    PocketAction::Archive(_) | PocketAction::Readd(_) => touched.status = true,
+                    PocketAction::Favorite(_) | PocketAction::Unfavorite(_) => {
+                        touched.favorite = true
+                    }
+                    PocketAction::Delete(_) => touched.deleted = true,
+                    PocketAction::TagsAdd(_)
+                    | PocketAction::TagsRemove(_)
+                    | PocketAction::TagsReplace(_)
+                    | PocketAction::TagsClear(_)
+                    | PocketAction::TagRename(_)
+                    | PocketAction::TagDelete(_) => touched.tags = true,
+                }
+            }
+        }
+
+        let snapshots: Vec<ItemSnapshot> = touched
+            .iter()
+            .map(|(item, _)| ItemSnapshot::from(*item))
+            .collect();
+        let inverse = snapshots
+            .iter()
+            .zip(touched.iter().map(|(_, touched)| touched))
+            .flat_map(|(snapshot, touched)| inverse_actions(snapshot, touched))
+            .collect();
+
+        Self {
+            created: Timestamp::now(),
+            actions,
+            snapshots,
+            inverse,
+        }
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), Error> {
+        let file = File::create(path)?;
+        serde_json::to_writer_pretty(BufWriter::new(file), self)?;
+        Ok(())
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let file = File::open(path)?;
+        Ok(serde_json::from_reader(BufReader::new(file))?)
+    }
+}
+
+fn inverse_actions(snapshot: &ItemSnapshot, touched: &Touched) -> Vec<PocketAction> {
+    let item_id = &snapshot.item_id;
+
+    if touched.deleted {
+        let Some(url) = &snapshot.url else {
+            return vec![];
+        };
+        // Pocket's item ids belong to the url rather than the save, so a
+        // re-added item gets its old id back and its status can be restored
+        // by id in the same request
+        let mut actions = vec![PocketAction::Add(Add {
+            item_id: None,
+            ref_id: None,
+            tags: (!snapshot.tags.0.is_empty()).then(|| snapshot.tags.0.join(",")),
+            time: snapshot.time_added,
+            title: snapshot.title.clone(),
+            url: Some(url.clone()),
+        })];
+        if snapshot.favorite {
+            actions.push(PocketAction::favorite(item_id));
+        }
+        if snapshot.status == ItemStatus::Archived {
+            actions.push(PocketAction::archive(item_id));
+        }
+        return actions;
+    }
+
+    let mut actions = Vec::new();
+    if touched.status {
+        actions.push(match snapshot.status {
+            ItemStatus::Archived => PocketAction::archive(item_id),
+            ItemStatus::Normal | ItemStatus::Deleted => PocketAction::readd(item_id),
+        });
+    }
+    if touched.favorite {
+        actions.push(if snapshot.favorite {
+            PocketAction::favorite(item_id)
+        } else {
+            PocketAction::unfavorite(item_id)
+        });
+    }
+    if touched.tags {
+        actions.push(if snapshot.tags.0.is_empty() {
+            PocketAction::tags_clear(item_id)
+        } else {
+            PocketAction::tags_replace(item_id, snapshot.tags.clone())
+        });
+    }
+    actions
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn item(value: serde_json::Value) -> PocketItem {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn re_adds_deleted_items_with_their_status() {
+        let items = [item(json!({
+            "item_id": "1",
+            "given_url": "https://example.com/a",
+            "given_title": "A",
+            "status": "1",
+            "favorite": "1",
+            "time_added": "1700000000",
+            "tags": { "rust": {} },
+        }))];
+
+        let journal = Journal::record(vec![PocketAction::delete("1")], &items);
+
+        assert_eq!(
+            journal.inverse,
+            vec![
+                PocketAction::Add(Add {
+                    item_id: None,
+                    ref_id: None,
+                    tags: Some("rust".to_string()),
+                    time: Some(Timestamp(1_700_000_000)),
+                    title: Some("A".to_string()),
+                    url: Some("https://example.com/a".to_string()),
+                }),
+                PocketAction::favorite("1"),
+                PocketAction::archive("1"),
+            ]
+        );
+    }
+
+    #[test]
+    fn restores_the_tags_of_every_renamed_item() {
+        let items = [
+            item(json!({ "item_id": "1", "status": "0", "tags": { "rustlang": {}, "async": {} } })),
+            item(json!({ "item_id": "2", "status": "0", "tags": { "rustlang": {} } })),
+            item(json!({ "item_id": "3", "status": "0", "tags": { "go": {} } })),
+        ];
+
+        let journal = Journal::record(vec![PocketAction::tag_rename("rustlang", "rust")], &items);
+
+        assert_eq!(
+            journal.inverse,
+            vec![
+                PocketAction::tags_replace("1", ["async", "rustlang"]),
+                PocketAction::tags_replace("2", ["rustlang"]),
+            ]
+        );
+    }
+
+    #[test]
+    fn leaves_added_urls_alone() {
+        let journal = Journal::record(vec![PocketAction::add("https://example.com/a")], &[]);
+        assert!(journal.inverse.is_empty());
+    }
+}
@@ -1,3 +1,10 @@
 pub mod add;
+pub mod batch_add;
+pub mod bulk;
+pub mod import;
+pub mod item;
+pub mod journal;
 pub mod modify;
+pub mod queue;
 pub mod retrieve;
+pub mod tags;
@@ -1,13 +1,28 @@
-use futures::TryFutureExt;
-use serde::{Deserialize, Serialize};
+use std::{
+    fmt::{Debug, Formatter},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+
+use futures::{stream, StreamExt};
+use serde::{de, Deserialize, Serialize};
 
 use crate::{
-    models::{ItemId, Tags, Timestamp},
-    ApiResult, Pockety, PocketyResponse,
+    api::journal::Journal,
+    models::{serialize_comma_separated, DetailType, ItemId, State, Tags, Timestamp},
+    query::Query,
+    ApiResult, Error, Pockety, PocketyResponse, RateLimits,
 };
 
-#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
-#[serde(untagged)]
+/// An action for the `/v3/send` endpoint.
+///
+/// Prefer the constructors (`PocketAction::archive(id)`, ...) over building
+/// the variants by hand. The `action` sent to Pocket is always taken from the
+/// variant, so `Archive(Update { action: UpdateName::Delete, .. })` archives.
+#[derive(Debug, PartialEq, Clone)]
 pub enum PocketAction {
     Add(Add),
     Archive(Update),
@@ -23,14 +38,257 @@ pub enum PocketAction {
     TagDelete(TagDelete),
 }
 
+impl PocketAction {
+    /// Add a new item by url
+    pub fn add(url: impl Into<String>) -> Self {
+        PocketAction::Add(Add {
+            item_id: None,
+            ref_id: None,
+            tags: None,
+            time: None,
+            title: None,
+            url: Some(url.into()),
+        })
+    }
+
+    /// Move an item to the archive
+    pub fn archive(item_id: impl Into<ItemId>) -> Self {
+        PocketAction::Archive(Update::new(UpdateName::Archive, item_id))
+    }
+
+    /// Move an item from the archive back to the list
+    pub fn readd(item_id: impl Into<ItemId>) -> Self {
+        PocketAction::Readd(Update::new(UpdateName::Readd, item_id))
+    }
+
+    pub fn favorite(item_id: impl Into<ItemId>) -> Self {
+        PocketAction::Favorite(Update::new(UpdateName::Favorite, item_id))
+    }
+
+    pub fn unfavorite(item_id: impl Into<ItemId>) -> Self {
+        PocketAction::Unfavorite(Update::new(UpdateName::Unfavorite, item_id))
+    }
+
+    /// Permanently remove an item
+    pub fn delete(item_id: impl Into<ItemId>) -> Self {
+        PocketAction::Delete(Update::new(UpdateName::Delete, item_id))
+    }
+
+    pub fn tags_add(item_id: impl Into<ItemId>, tags: impl Into<Tags>) -> Self {
+        PocketAction::TagsAdd(TagsAdd {
+            item_id: item_id.into(),
+            tags: tags.into(),
+            time: None,
+        })
+    }
+
+    pub fn tags_remove(item_id: impl Into<ItemId>, tags: impl Into<Tags>) -> Self {
+        PocketAction::TagsRemove(TagsRemove {
+            item_id: item_id.into(),
+            tags: tags.into(),
+            time: None,
+        })
+    }
+
+    /// Replace all of an item's tags
+    pub fn tags_replace(item_id: impl Into<ItemId>, tags: impl Into<Tags>) -> Self {
+        PocketAction::TagsReplace(TagsReplace {
+            item_id: item_id.into(),
+            tags: tags.into(),
+            time: None,
+        })
+    }
+
+    /// Remove all of an item's tags
+    pub fn tags_clear(item_id: impl Into<ItemId>) -> Self {
+        PocketAction::TagsClear(TagsClear {
+            item_id: item_id.into(),
+            time: None,
+        })
+    }
+
+    /// Rename a tag across every item that has it
+    pub fn tag_rename(old_tag: impl Into<String>, new_tag: impl Into<String>) -> Self {
+        PocketAction::TagRename(TagRename {
+            old_tag: old_tag.into(),
+            new_tag: new_tag.into(),
+            time: None,
+        })
+    }
+
+    /// Remove a tag from every item that has it
+    pub fn tag_delete(tag: impl Into<String>) -> Self {
+        PocketAction::TagDelete(TagDelete {
+            tag: tag.into(),
+            time: None,
+        })
+    }
+
+    /// The item the action applies to. `None` for actions on tags and for
+    /// adds by url.
+    pub fn item_id(&self) -> Option<&ItemId> {
+        match self {
+            PocketAction::Add(add) => add.item_id.as_ref(),
+            PocketAction::Archive(update)
+            | PocketAction::Readd(update)
+            | PocketAction::Favorite(update)
+            | PocketAction::Unfavorite(update)
+            | PocketAction::Delete(update) => Some(&update.item_id),
+            PocketAction::TagsAdd(TagsAdd { item_id, .. })
+            | PocketAction::TagsRemove(TagsRemove { item_id, .. })
+            | PocketAction::TagsReplace(TagsReplace { item_id, .. })
+            | PocketAction::TagsClear(TagsClear { item_id, .. }) => Some(item_id),
+            PocketAction::TagRename(_) | PocketAction::TagDelete(_) => None,
+        }
+    }
+
+    /// Sets the time the action happened. Item updates default to now, the
+    /// rest default to the time Pocket receives them.
+    pub fn at(self, time: impl Into<Timestamp>) -> Self {
+        let time = time.into();
+        match self {
+            PocketAction::Add(add) => PocketAction::Add(Add {
+                time: Some(time),
+                ..add
+            }),
+            PocketAction::Archive(update) => PocketAction::Archive(Update { time, ..update }),
+            PocketAction::Readd(update) => PocketAction::Readd(Update { time, ..update }),
+            PocketAction::Favorite(update) => PocketAction::Favorite(Update { time, ..update }),
+            PocketAction::Unfavorite(update) => PocketAction::Unfavorite(Update { time, ..update }),
+            PocketAction::Delete(update) => PocketAction::Delete(Update { time, ..update }),
+            PocketAction::TagsAdd(tags_add) => PocketAction::TagsAdd(TagsAdd {
+                time: Some(time),
+                ..tags_add
+            }),
+            PocketAction::TagsRemove(tags_remove) => PocketAction::TagsRemove(TagsRemove {
+                time: Some(time),
+                ..tags_remove
+            }),
+            PocketAction::TagsReplace(tags_replace) => PocketAction::TagsReplace(TagsReplace {
+                time: Some(time),
+                ..tags_replace
+            }),
+            PocketAction::TagsClear(tags_clear) => PocketAction::TagsClear(TagsClear {
+                time: Some(time),
+                ..tags_clear
+            }),
+            PocketAction::TagRename(tag_rename) => PocketAction::TagRename(TagRename {
+                time: Some(time),
+                ..tag_rename
+            }),
+            PocketAction::TagDelete(tag_delete) => PocketAction::TagDelete(TagDelete {
+                time: Some(time),
+                ..tag_delete
+            }),
+        }
+    }
+}
+
+impl Serialize for PocketAction {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        #[derive(Serialize)]
+        struct UpdateRef<'a> {
+            action: UpdateName,
+            item_id: &'a ItemId,
+            time: &'a Timestamp,
+        }
+
+        let (action, update) = match self {
+            PocketAction::Archive(archive) => (UpdateName::Archive, archive),
+            PocketAction::Readd(readd) => (UpdateName::Readd, readd),
+            PocketAction::Favorite(favorite) => (UpdateName::Favorite, favorite),
+            PocketAction::Unfavorite(unfavorite) => (UpdateName::Unfavorite, unfavorite),
+            PocketAction::Delete(delete) => (UpdateName::Delete, delete),
+            PocketAction::Add(add) => return add.serialize(serializer),
+            PocketAction::TagsAdd(tags_add) => return tags_add.serialize(serializer),
+            PocketAction::TagsRemove(tags_remove) => return tags_remove.serialize(serializer),
+            PocketAction::TagsReplace(tags_replace) => return tags_replace.serialize(serializer),
+            PocketAction::TagsClear(tags_clear) => return tags_clear.serialize(serializer),
+            PocketAction::TagRename(tag_rename) => return tag_rename.serialize(serializer),
+            PocketAction::TagDelete(tag_delete) => return tag_delete.serialize(serializer),
+        };
+
+        UpdateRef {
+            action,
+            item_id: &update.item_id,
+            time: &update.time,
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for PocketAction {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        fn from_value<T, E>(value: serde_json::Value) -> Result<T, E>
+        where
+            T: de::DeserializeOwned,
+            E: de::Error,
+        {
+            serde_json::from_value(value).map_err(E::custom)
+        }
+
+        let value = serde_json::Value::deserialize(deserializer)?;
+        let action = value
+            .get("action")
+            .and_then(serde_json::Value::as_str)
+            .ok_or_else(|| de::Error::missing_field("action"))?;
+
+        match action {
+            "add" => from_value(value).map(PocketAction::Add),
+            "archive" => from_value(value).map(PocketAction::Archive),
+            "readd" => from_value(value).map(PocketAction::Readd),
+            "favorite" => from_value(value).map(PocketAction::Favorite),
+            "unfavorite" => from_value(value).map(PocketAction::Unfavorite),
+            "delete" => from_value(value).map(PocketAction::Delete),
+            "tags_add" => from_value(value).map(PocketAction::TagsAdd),
+            "tags_remove" => from_value(value).map(PocketAction::TagsRemove),
+            "tags_replace" => from_value(value).map(PocketAction::TagsReplace),
+            "tags_clear" => from_value(value).map(PocketAction::TagsClear),
+            "tag_rename" => from_value(value).map(PocketAction::TagRename),
+            "tag_delete" => from_value(value).map(PocketAction::TagDelete),
+            action => Err(de::Error::unknown_variant(
+                action,
+                &[
+                    "add",
+                    "archive",
+                    "readd",
+                    "favorite",
+                    "unfavorite",
+                    "delete",
+                    "tags_add",
+                    "tags_remove",
+                    "tags_replace",
+                    "tags_clear",
+                    "tag_rename",
+                    "tag_delete",
+                ],
+            )),
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
 #[serde(tag = "action", rename = "add")]
 pub struct Add {
-    pub item_id: ItemId,
+    /// Pocket accepts either an `item_id` or a `url` for new items
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub item_id: Option<ItemId>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub ref_id: Option<u32>,
+    /// Comma separated list of tags
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub tags: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub time: Option<Timestamp>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub title: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub url: Option<String>,
 }
 
@@ -58,27 +316,44 @@ pub struct Update {
     pub time: Timestamp,
 }
 
+impl Update {
+    /// An update happening now
+    pub fn new(action: UpdateName, item_id: impl Into<ItemId>) -> Self {
+        Self {
+            action,
+            item_id: item_id.into(),
+            time: Timestamp::now(),
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
 #[serde(tag = "action", rename = "tags_add")]
 pub struct TagsAdd {
     pub item_id: ItemId,
+    #[serde(serialize_with = "serialize_comma_separated")]
     pub tags: Tags,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub time: Option<Timestamp>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
 #[serde(tag = "action", rename = "tags_replace")]
 pub struct TagsReplace {
-    item_id: ItemId,
-    tags: Tags,
-    time: Option<Timestamp>,
+    pub item_id: ItemId,
+    #[serde(serialize_with = "serialize_comma_separated")]
+    pub tags: Tags,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub time: Option<Timestamp>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
 #[serde(tag = "action", rename = "tags_remove")]
 pub struct TagsRemove {
     pub item_id: ItemId,
+    #[serde(serialize_with = "serialize_comma_separated")]
     pub tags: Tags,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub time: Option<Timestamp>,
 }
 
@@ -86,6 +361,7 @@ pub struct TagsRemove {
 #[serde(tag = "action", rename = "tags_clear")]
 pub struct TagsClear {
     pub item_id: ItemId,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub time: Option<Timestamp>,
 }
 
@@ -94,6 +370,7 @@ pub struct TagsClear {
 pub struct TagRename {
     pub old_tag: String,
     pub new_tag: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub time: Option<Timestamp>,
 }
 
@@ -101,6 +378,7 @@ pub struct TagRename {
 #[serde(tag = "action", rename = "tag_delete")]
 pub struct TagDelete {
     pub tag: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub time: Option<Timestamp>,
 }
 
@@ -114,13 +392,162 @@ pub struct ModifyRequestBody {
 #[derive(Serialize, Deserialize, Debug, Default)]
 pub struct ModifyResponse {
     pub status: u16,
-    pub action_results: Vec<bool>,
+    pub action_results: Vec<ActionResult>,
+    /// One entry per action, `None` for actions that succeeded
+    #[serde(default)]
+    pub action_errors: Vec<Option<ActionError>>,
+}
+
+/// What Pocket returns for a single action: a success flag, or for `add`
+/// actions the item that was added
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(untagged)]
+pub enum ActionResult {
+    Success(bool),
+    Item(serde_json::Value),
+}
+
+impl ActionResult {
+    pub fn is_success(&self) -> bool {
+        match self {
+            ActionResult::Success(success) => *success,
+            ActionResult::Item(item) => !item.is_null(),
+        }
+    }
+}
+
+impl Default for ActionResult {
+    fn default() -> Self {
+        ActionResult::Success(false)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ActionError {
+    pub message: Option<String>,
+    #[serde(rename = "type")]
+    pub error_type: Option<String>,
+    pub code: Option<u32>,
+}
+
+/// How far a chunked `/v3/send` got before a chunk failed
+#[derive(Debug)]
+pub struct PartialSend {
+    /// The chunks Pocket applied
+    pub outcome: ModifyOutcome,
+    /// The actions of the chunks that failed or weren't sent, in order
+    pub unsent: Vec<PocketAction>,
+    /// Why the first chunk failed
+    pub error: Box<Error>,
+}
+
+/// A submitted action together with how Pocket handled it
+#[derive(Debug, Clone, PartialEq)]
+pub struct ActionOutcome {
+    pub action: PocketAction,
+    pub result: ActionResult,
+    pub error: Option<ActionError>,
+}
+
+impl ActionOutcome {
+    pub fn is_success(&self) -> bool {
+        self.result.is_success()
+    }
+
+    pub fn error_message(&self) -> Option<&str> {
+        self.error.as_ref()?.message.as_deref()
+    }
+}
+
+/// The result of a `/v3/send` request, in the order the actions were pushed
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ModifyOutcome {
+    pub actions: Vec<ActionOutcome>,
+}
+
+impl ModifyOutcome {
+    /// Pairs the actions with Pocket's results. Actions Pocket didn't report
+    /// on are treated as failed.
+    pub fn new(actions: Vec<PocketAction>, response: ModifyResponse) -> Self {
+        let mut results = response.action_results.into_iter();
+        let mut errors = response.action_errors.into_iter();
+
+        let actions = actions
+            .into_iter()
+            .map(|action| ActionOutcome {
+                action,
+                result: results.next().unwrap_or_default(),
+                error: errors.next().flatten(),
+            })
+            .collect();
+
+        Self { actions }
+    }
+
+    pub fn succeeded(&self) -> impl Iterator<Item = &ActionOutcome> {
+        self.actions.iter().filter(|outcome| outcome.is_success())
+    }
+
+    pub fn failed(&self) -> impl Iterator<Item = &ActionOutcome> {
+        self.actions.iter().filter(|outcome| !outcome.is_success())
+    }
+
+    pub fn all_succeeded(&self) -> bool {
+        self.actions.iter().all(ActionOutcome::is_success)
+    }
+
+    pub fn len(&self) -> usize {
+        self.actions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.actions.is_empty()
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, ActionOutcome> {
+        self.actions.iter()
+    }
+}
+
+impl IntoIterator for ModifyOutcome {
+    type Item = ActionOutcome;
+    type IntoIter = std::vec::IntoIter<ActionOutcome>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.actions.into_iter()
+    }
+}
+
+/// The `chunk_size` the handlers that send many actions at once default to
+pub const DEFAULT_CHUNK_SIZE: usize = 100;
+
+/// How far a chunked [`ModifyHandler::send`] has got
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ModifyProgress {
+    pub chunks_sent: usize,
+    pub chunks_total: usize,
+    pub actions_sent: usize,
+    pub actions_total: usize,
+}
+
+#[derive(Clone)]
+struct ProgressCallback(Arc<dyn Fn(ModifyProgress) + Send + Sync>);
+
+impl Debug for ProgressCallback {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str("ProgressCallback")
+    }
 }
 
 #[derive(Debug)]
 pub struct ModifyHandler<'po> {
     pockety: &'po Pockety,
     body: ModifyRequestBody,
+    fail_on_action_errors: bool,
+    chunk_size: Option<usize>,
+    concurrency: usize,
+    on_progress: Option<ProgressCallback>,
+    journal: Option<PathBuf>,
 }
 
 impl<'po> ModifyHandler<'po> {
@@ -128,6 +555,11 @@ impl<'po> ModifyHandler<'po> {
         Self {
             pockety,
             body: Default::default(),
+            fail_on_action_errors: false,
+            chunk_size: None,
+            concurrency: 1,
+            on_progress: None,
+            journal: None,
         }
     }
 
@@ -141,18 +573,251 @@ impl<'po> ModifyHandler<'po> {
         self
     }
 
-    pub async fn send(self) -> ApiResult<Vec<bool>> {
-        let body = ModifyRequestBody {
-            consumer_key: self.pockety.consumer_key.clone(),
-            ..self.body
-        };
+    /// Return [`Error::ActionsFailed`] if any of the actions fail, rather than
+    /// reporting the failures in the [`ModifyOutcome`]
+    pub fn fail_on_action_errors(mut self, fail_on_action_errors: bool) -> Self {
+        self.fail_on_action_errors = fail_on_action_errors;
+        self
+    }
+
+    /// Split the actions into requests of at most `chunk_size` actions. By
+    /// default all actions are sent in a single request.
+    pub fn chunk_size(mut self, chunk_size: usize) -> Self {
+        self.chunk_size = Some(chunk_size.max(1));
+        self
+    }
+
+    /// How many chunks may be in flight at once. Defaults to 1, i.e. chunks
+    /// are sent one after the other. Results are reported in the order the
+    /// actions were pushed regardless.
+    pub fn concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    /// Called after each chunk has been sent
+    pub fn on_progress<F>(mut self, on_progress: F) -> Self
+    where
+        F: Fn(ModifyProgress) + Send + Sync + 'static,
+    {
+        self.on_progress = Some(ProgressCallback(Arc::new(on_progress)));
+        self
+    }
+
+    /// Before sending, record the current state of every item the actions
+    /// touch and write a [`Journal`] that can undo the batch to `path`. This
+    /// retrieves the user's whole list first.
+    pub fn journal(mut self, path: impl Into<PathBuf>) -> Self {
+        self.journal = Some(path.into());
+        self
+    }
+
+    /// Push the actions that undo a previously journaled batch. `add`
+    /// actions in the batch aren't undone, so urls it added stay in the list.
+    pub fn undo(self, journal: &Journal) -> Self {
+        let now = Timestamp::now();
+        self.extend(journal.inverse.iter().map(|action| action.clone().at(now)))
+    }
+
+    pub fn extend(mut self, actions: impl IntoIterator<Item = PocketAction>) -> Self {
+        self.body.actions.extend(actions);
+        self
+    }
+
+    /// Add a new item by url. See also
+    /// [`BatchAddHandler`](crate::api::batch_add::BatchAddHandler).
+    pub fn add_url(self, url: impl Into<String>) -> Self {
+        self.push(PocketAction::add(url))
+    }
 
-        self.pockety
-            .post::<ModifyRequestBody, ModifyResponse>("/send", Some(&body))
-            .map_ok(|res| PocketyResponse {
-                rate_limits: res.rate_limits,
-                data: res.data.action_results,
+    pub fn archive(self, item_id: impl Into<ItemId>) -> Self {
+        self.push(PocketAction::archive(item_id))
+    }
+
+    pub fn readd(self, item_id: impl Into<ItemId>) -> Self {
+        self.push(PocketAction::readd(item_id))
+    }
+
+    pub fn favorite(self, item_id: impl Into<ItemId>) -> Self {
+        self.push(PocketAction::favorite(item_id))
+    }
+
+    pub fn unfavorite(self, item_id: impl Into<ItemId>) -> Self {
+        self.push(PocketAction::unfavorite(item_id))
+    }
+
+    pub fn delete(self, item_id: impl Into<ItemId>) -> Self {
+        self.push(PocketAction::delete(item_id))
+    }
+
+    pub fn tags_add(self, item_id: impl Into<ItemId>, tags: impl Into<Tags>) -> Self {
+        self.push(PocketAction::tags_add(item_id, tags))
+    }
+
+    pub fn tags_remove(self, item_id: impl Into<ItemId>, tags: impl Into<Tags>) -> Self {
+        self.push(PocketAction::tags_remove(item_id, tags))
+    }
+
+    pub fn tags_replace(self, item_id: impl Into<ItemId>, tags: impl Into<Tags>) -> Self {
+        self.push(PocketAction::tags_replace(item_id, tags))
+    }
+
+    pub fn tags_clear(self, item_id: impl Into<ItemId>) -> Self {
+        self.push(PocketAction::tags_clear(item_id))
+    }
+
+    pub fn tag_rename(self, old_tag: impl Into<String>, new_tag: impl Into<String>) -> Self {
+        self.push(PocketAction::tag_rename(old_tag, new_tag))
+    }
+
+    pub fn tag_delete(self, tag: impl Into<String>) -> Self {
+        self.push(PocketAction::tag_delete(tag))
+    }
+
+    /// Sends the actions, in chunks if [`chunk_size`](Self::chunk_size) is
+    /// set. Stops at the first chunk that fails outright. If Pocket had
+    /// already applied other chunks by then, the error is
+    /// [`Error::PartiallySent`] with their outcome and the actions that
+    /// weren't applied.
+    pub async fn send(self) -> ApiResult<ModifyOutcome> {
+        if let Some(path) = &self.journal {
+            self.write_journal(path).await?;
+        }
+
+        let ModifyRequestBody {
+            access_token,
+            mut actions,
+            ..
+        } = self.body;
+        let consumer_key = &self.pockety.consumer_key;
+        let pockety = self.pockety;
+
+        let actions_total = actions.len();
+        let chunk_size = self.chunk_size.unwrap_or(actions_total).max(1);
+        let mut chunks = Vec::new();
+        while !actions.is_empty() {
+            let rest = actions.split_off(chunk_size.min(actions.len()));
+            chunks.push(std::mem::replace(&mut actions, rest));
+        }
+        let chunks_total = chunks.len();
+
+        // once a chunk fails the ones that haven't started are left unsent,
+        // while those already in flight finish and are accounted for
+        let stopped = AtomicBool::new(false);
+        let mut responses = stream::iter(chunks)
+            .map(|actions| {
+                let body = ModifyRequestBody {
+                    consumer_key: consumer_key.clone(),
+                    access_token: access_token.clone(),
+                    actions,
+                };
+                let stopped = &stopped;
+                async move {
+                    if stopped.load(Ordering::SeqCst) {
+                        return Err((body.actions, None));
+                    }
+                    match pockety
+                        .post::<ModifyRequestBody, ModifyResponse>("/send", Some(&body))
+                        .await
+                    {
+                        Ok(response) => Ok((
+                            response.rate_limits,
+                            ModifyOutcome::new(body.actions, response.data),
+                        )),
+                        Err(error) => {
+                            stopped.store(true, Ordering::SeqCst);
+                            Err((body.actions, Some(error)))
+                        }
+                    }
+                }
             })
-            .await
+            .buffered(self.concurrency);
+
+        let mut rate_limits = RateLimits::default();
+        let mut outcome = ModifyOutcome::default();
+        let mut unsent = Vec::new();
+        let mut first_error = None;
+        let mut chunks_sent = 0;
+        while let Some(response) = responses.next().await {
+            let (chunk_rate_limits, chunk_outcome) = match response {
+                Ok(response) => response,
+                Err((actions, error)) => {
+                    unsent.extend(actions);
+                    first_error = first_error.or(error);
+                    continue;
+                }
+            };
+            rate_limits = chunk_rate_limits;
+            outcome.actions.extend(chunk_outcome.actions);
+            chunks_sent += 1;
+
+            if let Some(ProgressCallback(on_progress)) = &self.on_progress {
+                on_progress(ModifyProgress {
+                    chunks_sent,
+                    chunks_total,
+                    actions_sent: outcome.len(),
+                    actions_total,
+                });
+            }
+        }
+
+        drop(responses);
+        if let Some(error) = first_error {
+            if outcome.is_empty() {
+                return Err(error);
+            }
+            return Err(Error::PartiallySent(PartialSend {
+                outcome,
+                unsent,
+                error: Box::new(error),
+            }));
+        }
+
+        if self.fail_on_action_errors && !outcome.all_succeeded() {
+            return Err(Error::ActionsFailed(outcome));
+        }
+
+        Ok(PocketyResponse {
+            rate_limits,
+            data: outcome,
+        })
+    }
+
+    async fn write_journal(&self, path: &Path) -> Result<(), Error> {
+        let items = self
+            .pockety
+            .retrieve()
+            .access_token(self.body.access_token.clone())
+            .detail_type(DetailType::Complete)
+            .execute_query(&Query::new().state(State::All))
+            .await?;
+
+        Journal::record(self.body.actions.clone(), &items.data).save(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn sends_tags_comma_separated_without_a_time() {
+        let actions = [
+            PocketAction::tags_add("1", ["rust", "async"]),
+            PocketAction::tags_remove("1", ["rust", "async"]),
+            PocketAction::tags_replace("1", ["rust", "async"]),
+        ];
+        for action in actions {
+            let value = serde_json::to_value(&action).unwrap();
+            assert_eq!(value["item_id"], json!("1"));
+            assert_eq!(value["tags"], json!("rust,async"));
+            assert!(value.get("time").is_none());
+            assert_eq!(
+                serde_json::from_value::<PocketAction>(value).unwrap(),
+                action
+            );
+        }
     }
 }
@@ -0,0 +1,386 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fs::{self, File, OpenOptions},
+    io::{BufRead, BufReader, BufWriter, Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, MutexGuard, OnceLock, PoisonError},
+    time::Duration,
+};
+
+use crate::{
+    api::modify::{ModifyOutcome, PocketAction, DEFAULT_CHUNK_SIZE},
+    models::{ItemId, Timestamp},
+    ApiResult, Error, Pockety, PocketyResponse, RateLimits,
+};
+
+/// A durable queue of actions waiting to be sent, e.g. while offline.
+///
+/// Actions are appended to a JSON Lines file as they are pushed, so nothing is
+/// lost if the process exits before the queue is flushed. Actions can be
+/// pushed while the queue is flushing, from clones of the queue or queues
+/// opened on the same path, and are sent by the next flush. Only one process
+/// should use a given file at a time.
+#[derive(Debug, Clone)]
+pub struct ActionQueue {
+    path: PathBuf,
+    chunk_size: usize,
+    max_retries: u32,
+    retry_delay: Duration,
+    locks: Arc<QueueLocks>,
+}
+
+/// Shared by every queue on the same file in this process
+#[derive(Debug, Default)]
+struct QueueLocks {
+    /// Held while the file is read or written
+    file: Mutex<()>,
+    /// Held for the whole of a flush
+    flush: tokio::sync::Mutex<()>,
+}
+
+impl QueueLocks {
+    fn for_path(path: &Path) -> Arc<Self> {
+        static LOCKS: OnceLock<Mutex<HashMap<PathBuf, Arc<QueueLocks>>>> = OnceLock::new();
+
+        let path = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+        LOCKS
+            .get_or_init(Default::default)
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .entry(path)
+            .or_default()
+            .clone()
+    }
+
+    fn file(&self) -> MutexGuard<'_, ()> {
+        self.file.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl ActionQueue {
+    pub const DEFAULT_MAX_RETRIES: u32 = 3;
+    pub const DEFAULT_RETRY_DELAY: Duration = Duration::from_secs(1);
+
+    /// Opens the queue stored at `path`, creating the file if needed
+    pub fn open(path: impl Into<PathBuf>) -> Result<Self, Error> {
+        let path = path.into();
+        OpenOptions::new().create(true).append(true).open(&path)?;
+
+        Ok(Self {
+            locks: QueueLocks::for_path(&path),
+            path,
+            chunk_size: DEFAULT_CHUNK_SIZE,
+            max_retries: Self::DEFAULT_MAX_RETRIES,
+            retry_delay: Self::DEFAULT_RETRY_DELAY,
+        })
+    }
+
+    /// How many actions to send per `/v3/send` request when flushing
+    pub fn chunk_size(mut self, chunk_size: usize) -> Self {
+        self.chunk_size = chunk_size.max(1);
+        self
+    }
+
+    /// How often to retry a chunk that failed with a retryable error
+    pub fn max_retries(mut self, max_retries: u32) -> Self {
+        self.max_retries = max_retries;
+        self
+    }
+
+    /// Delay before the first retry; doubled for every retry after that
+    pub fn retry_delay(mut self, retry_delay: Duration) -> Self {
+        self.retry_delay = retry_delay;
+        self
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn push(&self, action: &PocketAction) -> Result<(), Error> {
+        self.extend(std::iter::once(action))
+    }
+
+    pub fn extend<'a>(
+        &self,
+        actions: impl IntoIterator<Item = &'a PocketAction>,
+    ) -> Result<(), Error> {
+        let _lock = self.locks.file();
+        let mut file = OpenOptions::new()
+            .read(true)
+            .append(true)
+            .open(&self.path)?;
+
+        // finish off a line left truncated by an interrupted write, so that
+        // only that line is lost rather than the next one too
+        let mut last = [0];
+        if file.seek(SeekFrom::End(-1)).is_ok()
+            && file.read_exact(&mut last).is_ok()
+            && last[0] != b'\n'
+        {
+            file.write_all(b"\n")?;
+        }
+
+        let mut writer = BufWriter::new(file);
+        for action in actions {
+            serde_json::to_writer(&mut writer, action)?;
+            writer.write_all(b"\n")?;
+        }
+        writer
+            .into_inner()
+            .map_err(|e| e.into_error())?
+            .sync_data()?;
+        Ok(())
+    }
+
+    /// The queued actions, oldest first. Truncated lines, left behind by
+    /// writes that were interrupted, are skipped.
+    pub fn pending(&self) -> Result<Vec<PocketAction>, Error> {
+        let _lock = self.locks.file();
+        self.read()
+    }
+
+    fn read(&self) -> Result<Vec<PocketAction>, Error> {
+        let reader = BufReader::new(File::open(&self.path)?);
+        let lines = reader.lines().collect::<Result<Vec<_>, _>>()?;
+        let last = lines.len().saturating_sub(1);
+
+        let mut actions = Vec::new();
+        for (index, line) in lines.iter().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            match serde_json::from_str(line) {
+                Ok(action) => actions.push(action),
+                Err(error) if error.is_eof() || index == last => {}
+                Err(error) => return Err(error.into()),
+            }
+        }
+        Ok(actions)
+    }
+
+    pub fn is_empty(&self) -> Result<bool, Error> {
+        Ok(self.pending()?.is_empty())
+    }
+
+    pub fn clear(&self) -> Result<(), Error> {
+        let _lock = self.locks.file();
+        self.replace(&[])
+    }
+
+    /// Sends the queued actions, after [`coalesce`]-ing them, in chunks.
+    ///
+    /// Each chunk is retried with exponential backoff while it fails with a
+    /// [retryable](Error::is_retryable) error and removed from the queue once
+    /// Pocket has handled it. Actions Pocket rejects individually are not
+    /// retried; they are reported in the returned [`ModifyOutcome`]. If a
+    /// chunk can't be sent the error is returned and it stays queued, along
+    /// with every chunk after it.
+    ///
+    /// Actions pushed while the queue is flushing stay queued for the next
+    /// flush, even if the queue is cleared meanwhile, and flushes of the same
+    /// file wait for each other.
+    pub async fn flush(
+        &self,
+        pockety: &Pockety,
+        access_token: impl Into<String>,
+    ) -> ApiResult<ModifyOutcome> {
+        let _flushing = self.locks.flush.lock().await;
+        let access_token = access_token.into();
+        let mut remaining = {
+            let _lock = self.locks.file();
+            let remaining = coalesce(self.read()?);
+            self.replace(&remaining)?;
+            remaining
+        };
+
+        let mut rate_limits = RateLimits::default();
+        let mut outcome = ModifyOutcome::default();
+        while !remaining.is_empty() {
+            let rest = remaining.split_off(self.chunk_size.min(remaining.len()));
+            let chunk = std::mem::replace(&mut remaining, rest);
+
+            let mut retries = 0;
+            let response = loop {
+                let result = pockety
+                    .modify()
+                    .access_token(access_token.clone())
+                    .extend(chunk.iter().cloned())
+                    .send()
+                    .await;
+
+                match result {
+                    Err(error) if error.is_retryable() && retries < self.max_retries => {
+                        tokio::time::sleep(self.retry_delay * 2u32.pow(retries)).await;
+                        retries += 1;
+                    }
+                    result => break result,
+                }
+            };
+
+            let response = response?;
+            self.remove_front(&chunk)?;
+            rate_limits = response.rate_limits;
+            outcome.actions.extend(response.data.actions);
+        }
+
+        Ok(PocketyResponse {
+            rate_limits,
+            data: outcome,
+        })
+    }
+
+    /// Removes a sent chunk from the front of the queue, where it stays
+    /// followed by the rest of the flush and anything pushed since, unless
+    /// the queue was cleared in the meantime
+    fn remove_front(&self, chunk: &[PocketAction]) -> Result<(), Error> {
+        let _lock = self.locks.file();
+        let actions = self.read()?;
+        match actions.strip_prefix(chunk) {
+            Some(rest) => self.replace(rest),
+            None => Ok(()),
+        }
+    }
+
+    /// Atomically replaces the contents of the queue. Callers hold the file
+    /// lock.
+    fn replace(&self, actions: &[PocketAction]) -> Result<(), Error> {
+        let tmp = self.path.with_extension("tmp");
+        let mut writer = BufWriter::new(File::create(&tmp)?);
+        for action in actions {
+            serde_json::to_writer(&mut writer, action)?;
+            writer.write_all(b"\n")?;
+        }
+        writer
+            .into_inner()
+            .map_err(|e| e.into_error())?
+            .sync_data()?;
+        fs::rename(tmp, &self.path)?;
+        Ok(())
+    }
+}
+
+/// Drops actions that a later action makes redundant, keeping the order of
+/// the rest:
+///
+/// - exact duplicates (ignoring `time`), keeping the last one
+/// - all but the last `archive`/`readd` and `favorite`/`unfavorite` of an item,
+///   e.g. archiving then re-adding an item only sends the `readd`
+/// - item tag changes followed by a `tags_replace` or `tags_clear` of the item
+/// - anything done to an item before it is deleted, and anything but `add`
+///   after it is deleted
+pub fn coalesce(actions: Vec<PocketAction>) -> Vec<PocketAction> {
+    let mut seen = HashSet::new();
+    let mut status = HashSet::new();
+    let mut favorite = HashSet::new();
+    let mut tags = HashSet::new();
+    let mut deleted: HashSet<ItemId> = HashSet::new();
+
+    // walk backwards so that the last action of each kind is the one seen first
+    let mut kept: Vec<PocketAction> = actions
+        .into_iter()
+        .rev()
+        .filter(|action| {
+            let key = serde_json::to_string(&action.clone().at(Timestamp(0))).unwrap_or_default();
+            if !seen.insert(key) {
+                return false;
+            }
+
+            let Some(item_id) = action.item_id() else {
+                return true;
+            };
+            if matches!(action, PocketAction::Add(_)) {
+                return true;
+            }
+            if deleted.contains(item_id) {
+                return false;
+            }
+
+            match action {
+                PocketAction::Delete(_) => {
+                    deleted.insert(item_id.clone());
+                    true
+                }
+                PocketAction::Archive(_) | PocketAction::Readd(_) => status.insert(item_id.clone()),
+                PocketAction::Favorite(_) | PocketAction::Unfavorite(_) => {
+                    favorite.insert(item_id.clone())
+                }
+                PocketAction::TagsReplace(_) | PocketAction::TagsClear(_) => {
+                    tags.insert(item_id.clone())
+                }
+                PocketAction::TagsAdd(_) | PocketAction::TagsRemove(_) => !tags.contains(item_id),
+                _ => true,
+            }
+        })
+        .collect();
+    kept.reverse();
+
+    // actions on an item after it has been deleted would fail
+    let mut deleted = HashSet::new();
+    kept.retain(|action| match (action, action.item_id()) {
+        (PocketAction::Add(_), _) | (_, None) => true,
+        (PocketAction::Delete(_), Some(item_id)) => deleted.insert(item_id.clone()),
+        (_, Some(item_id)) => !deleted.contains(item_id),
+    });
+
+    kept
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn queue(name: &str) -> ActionQueue {
+        let path =
+            std::env::temp_dir().join(format!("pockety-queue-{}-{name}.jsonl", std::process::id()));
+        let _ = fs::remove_file(&path);
+        ActionQueue::open(path).unwrap()
+    }
+
+    #[test]
+    fn coalesces_archive_then_readd_into_readd() {
+        let actions = coalesce(vec![
+            PocketAction::archive("1"),
+            PocketAction::favorite("2"),
+            PocketAction::readd("1"),
+        ]);
+
+        assert_eq!(
+            actions,
+            vec![PocketAction::favorite("2"), PocketAction::readd("1")]
+        );
+    }
+
+    #[test]
+    fn coalesces_actions_around_a_delete() {
+        let actions = coalesce(vec![
+            PocketAction::favorite("1"),
+            PocketAction::delete("1"),
+            PocketAction::archive("1"),
+            PocketAction::tags_add("1", "rust"),
+            PocketAction::archive("2"),
+        ]);
+
+        assert_eq!(
+            actions,
+            vec![PocketAction::delete("1"), PocketAction::archive("2")]
+        );
+    }
+
+    #[test]
+    fn skips_and_repairs_a_partially_written_last_line() {
+        let queue = queue("truncated");
+        queue.push(&PocketAction::archive("1")).unwrap();
+        let mut file = OpenOptions::new().append(true).open(queue.path()).unwrap();
+        file.write_all(br#"{"action":"archive","item_"#).unwrap();
+
+        assert_eq!(queue.pending().unwrap(), vec![PocketAction::archive("1")]);
+
+        queue.push(&PocketAction::favorite("2")).unwrap();
+        assert_eq!(
+            queue.pending().unwrap(),
+            vec![PocketAction::archive("1"), PocketAction::favorite("2")]
+        );
+        fs::remove_file(queue.path()).unwrap();
+    }
+}
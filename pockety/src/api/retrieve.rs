@@ -4,11 +4,15 @@ use futures::TryFutureExt;
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    models::{ContentType, DetailType, PocketItem, Sort, State, Tag, Timestamp},
+    models::{
+        deserialize_extra, ContentType, DetailType, PocketItem, SearchMeta, Sort, State, Tag,
+        Timestamp,
+    },
+    query::Query,
     ApiResult, Pockety, PocketyResponse,
 };
 
-#[derive(Serialize, Deserialize, Debug, Default)]
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
 pub struct RetrieveRequestBody {
     pub consumer_key: String,
     pub access_token: String,
@@ -16,7 +20,9 @@ pub struct RetrieveRequestBody {
     pub domain: Option<String>,
     pub tag: Option<Tag>,
     pub state: Option<State>,
+    #[serde(rename = "contentType")]
     pub content_type: Option<ContentType>,
+    #[serde(rename = "detailType")]
     pub detail_type: Option<DetailType>,
     pub favorite: Option<bool>,
     pub since: Option<Timestamp>,
@@ -27,15 +33,63 @@ pub struct RetrieveRequestBody {
 
 #[derive(Serialize, Deserialize, Debug, Default)]
 pub struct RetrieveResponse {
+    #[serde(deserialize_with = "deserialize_list")]
     pub list: HashMap<String, PocketItem>,
     pub status: u16,
     pub complete: u16,
     pub error: Option<String>,
     pub since: Option<i64>,
     // search_meta isn't documented in the API docs, but it's in the response.
-    // we're not too sure what we want to do with this, so we're just going to
-    // leave it as a serde_json::Value for now.
-    pub search_meta: Option<serde_json::Value>,
+    pub search_meta: Option<SearchMeta>,
+    /// Any fields Pocket sends that aren't modelled above
+    #[serde(flatten, deserialize_with = "deserialize_extra")]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+impl RetrieveResponse {
+    /// Total number of items matching a `search`, if Pocket reported one
+    pub fn total_result_count(&self) -> Option<u32> {
+        self.search_meta
+            .as_ref()
+            .and_then(|search_meta| search_meta.total_result_count)
+    }
+}
+
+/// The items of every page [`RetrieveHandler::execute_pages`] fetched
+pub(crate) struct Pages {
+    /// The `since` of the first page
+    pub since: Option<Timestamp>,
+    pub items: Vec<PocketItem>,
+}
+
+/// Pocket sends an empty list as `[]` rather than `{}`
+fn deserialize_list<'de, D>(deserializer: D) -> Result<HashMap<String, PocketItem>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum MapOrList {
+        Map(HashMap<String, PocketItem>),
+        List(Vec<PocketItem>),
+    }
+
+    match MapOrList::deserialize(deserializer)? {
+        MapOrList::Map(list) => Ok(list),
+        MapOrList::List(list) => Ok(list
+            .into_iter()
+            .map(|item| (item.item_id.0.clone(), item))
+            .collect()),
+    }
+}
+
+impl RetrieveResponse {
+    /// The items of the response in the order Pocket sorted them
+    pub fn sorted_items(self) -> Vec<PocketItem> {
+        let mut items: Vec<PocketItem> = self.list.into_values().collect();
+        items.sort_by_key(|item| item.sort_id);
+        items
+    }
 }
 
 #[derive(Debug)]
@@ -45,6 +99,10 @@ pub struct RetrieveHandler<'po> {
 }
 
 impl<'po> RetrieveHandler<'po> {
+    /// Number of items requested per page when paginating, which is also
+    /// the most Pocket returns for a single request
+    pub const PAGE_SIZE: u32 = 30;
+
     pub fn new(pockety: &'po Pockety) -> Self {
         Self {
             pockety,
@@ -67,8 +125,8 @@ impl<'po> RetrieveHandler<'po> {
         self
     }
 
-    pub fn tag(mut self, tag: Tag) -> Self {
-        self.body.tag = Some(tag);
+    pub fn tag(mut self, tag: impl Into<Tag>) -> Self {
+        self.body.tag = Some(tag.into());
         self
     }
 
@@ -112,8 +170,92 @@ impl<'po> RetrieveHandler<'po> {
         self
     }
 
-    #[cfg(not(feature = "debug"))]
     pub async fn execute(self) -> ApiResult<Vec<PocketItem>> {
+        self.execute_raw()
+            .map_ok(|res| PocketyResponse {
+                rate_limits: res.rate_limits,
+                data: res.data.sorted_items(),
+            })
+            .await
+    }
+
+    /// Pages through `/v3/get` and returns every item matching `query`.
+    ///
+    /// The predicates Pocket supports are sent with each request, the rest
+    /// are applied to each page as it comes back; see [`Query`] for which
+    /// runs where. Filters set on the handler itself are overridden by the
+    /// query where both set the same parameter. `count` sets the page size,
+    /// up to and defaulting to [`Self::PAGE_SIZE`], and `offset` the first
+    /// item to fetch.
+    pub async fn execute_query(mut self, query: &Query) -> ApiResult<Vec<PocketItem>> {
+        query.apply(&mut self.body);
+        self.execute_pages(|item| query.matches(item))
+            .map_ok(|res| PocketyResponse {
+                rate_limits: res.rate_limits,
+                data: res.data.items,
+            })
+            .await
+    }
+
+    /// Pages through `/v3/get` from `offset`, `count` items at a time, up to
+    /// and defaulting to [`Self::PAGE_SIZE`], keeping the items `keep`
+    /// accepts
+    pub(crate) async fn execute_pages(
+        self,
+        keep: impl Fn(&PocketItem) -> bool,
+    ) -> ApiResult<Pages> {
+        let body = RetrieveRequestBody {
+            consumer_key: self.pockety.consumer_key.clone(),
+            ..self.body
+        };
+
+        // Pocket caps pages at PAGE_SIZE items, so a larger page would look
+        // like the last one
+        let page_size = body
+            .count
+            .unwrap_or(Self::PAGE_SIZE)
+            .clamp(1, Self::PAGE_SIZE);
+        let mut offset = body.offset.unwrap_or_default();
+        let mut pages = Pages {
+            since: None,
+            items: Vec::new(),
+        };
+
+        loop {
+            let page = RetrieveRequestBody {
+                count: Some(page_size),
+                offset: Some(offset),
+                ..body.clone()
+            };
+            let response = self
+                .pockety
+                .post::<RetrieveRequestBody, RetrieveResponse>("/get", Some(&page))
+                .await?;
+
+            // the first page's `since` is from before any later page was
+            // read, so changes made while paging are picked up next time
+            pages.since = pages.since.or(response.data.since.map(Timestamp));
+            let page_items = response.data.sorted_items();
+            let is_last_page = (page_items.len() as u32) < page_size;
+            pages
+                .items
+                .extend(page_items.into_iter().filter(|item| keep(item)));
+
+            if is_last_page {
+                return Ok(PocketyResponse {
+                    rate_limits: response.rate_limits,
+                    data: pages,
+                });
+            }
+            offset += page_size;
+        }
+    }
+
+    /// Like [`execute`](Self::execute), but returns the whole response so that
+    /// `search_meta` (e.g. the total result count of a search) and `since` are
+    /// available too.
+    #[cfg(not(feature = "debug"))]
+    pub async fn execute_raw(self) -> ApiResult<RetrieveResponse> {
         let body = RetrieveRequestBody {
             consumer_key: self.pockety.consumer_key.clone(),
             ..self.body
@@ -121,15 +263,11 @@ impl<'po> RetrieveHandler<'po> {
 
         self.pockety
             .post::<RetrieveRequestBody, RetrieveResponse>("/get", Some(&body))
-            .map_ok(|res| PocketyResponse {
-                rate_limits: res.rate_limits,
-                data: res.data.list.into_values().collect(),
-            })
             .await
     }
 
     #[cfg(feature = "debug")]
-    pub async fn execute(self) -> ApiResult<Vec<PocketItem>> {
+    pub async fn execute_raw(self) -> ApiResult<RetrieveResponse> {
         let body = RetrieveRequestBody {
             consumer_key: self.pockety.consumer_key.clone(),
             ..self.body
@@ -137,10 +275,6 @@ impl<'po> RetrieveHandler<'po> {
 
         self.pockety
             .post::<RetrieveRequestBody, RetrieveResponse>("/get", Some(&body))
-            .map_ok(|res| PocketyResponse {
-                rate_limits: res.rate_limits,
-                data: res.data.list.into_values().collect(),
-            })
             .inspect_ok(|res| {
                 log::debug!("[POCKETY_RETRIEVE] Request Body: {body:?}, Response: {res:?}")
             })
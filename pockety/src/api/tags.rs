@@ -0,0 +1,254 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    api::{
+        bulk::BulkOutcome,
+        modify::{ModifyOutcome, PocketAction, DEFAULT_CHUNK_SIZE},
+    },
+    models::{DetailType, PocketItem, State, Tags},
+    query::Query,
+    ApiResult, Pockety, PocketyResponse, RateLimits,
+};
+
+/// A tag and how many items have it
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct TagCount {
+    pub tag: String,
+    pub count: usize,
+}
+
+/// Counts the tags of `items`, most used first and then by name. Items need
+/// their tags, i.e. be retrieved with `DetailType::Complete`.
+pub fn tag_counts<'a>(items: impl IntoIterator<Item = &'a PocketItem>) -> Vec<TagCount> {
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    for tag in items.into_iter().flat_map(PocketItem::tag_names) {
+        *counts.entry(tag).or_default() += 1;
+    }
+
+    let mut counts: Vec<TagCount> = counts
+        .into_iter()
+        .map(|(tag, count)| TagCount {
+            tag: tag.to_string(),
+            count,
+        })
+        .collect();
+    counts.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.tag.cmp(&b.tag)));
+    counts
+}
+
+/// The actions that merge the `sources` tags into `target` on `items`: every
+/// item with one of the sources gets `target` instead.
+///
+/// Merging is done item by item rather than with `tag_rename`, as Pocket
+/// doesn't document what renaming a tag onto an existing tag does.
+pub fn merge_actions<'a>(
+    items: impl IntoIterator<Item = &'a PocketItem>,
+    sources: &[String],
+    target: &str,
+) -> Vec<PocketAction> {
+    let mut actions = Vec::new();
+    for item in items {
+        let remove: Vec<&String> = sources
+            .iter()
+            .filter(|tag| *tag != target && item.has_tag(tag))
+            .collect();
+        if remove.is_empty() {
+            continue;
+        }
+        if !item.has_tag(target) {
+            actions.push(PocketAction::tags_add(&item.item_id, target));
+        }
+        actions.push(PocketAction::tags_remove(
+            &item.item_id,
+            remove.into_iter().cloned().collect::<Tags>(),
+        ));
+    }
+    actions
+}
+
+/// The result of a tag operation
+#[derive(Debug)]
+pub struct TagChange {
+    /// How many items had the tags that were changed
+    pub items_affected: usize,
+    pub outcome: BulkOutcome,
+}
+
+/// Manages a user's tags across their whole list. Pocket has no endpoint for
+/// tags, so every operation first retrieves the items with the tags involved.
+///
+/// ```no_run
+/// # async fn run(pockety: pockety::Pockety, access_token: String) -> Result<(), pockety::Error> {
+/// let tags = pockety.tags().access_token(access_token);
+///
+/// for tag in tags.list().await?.data {
+///     println!("{}: {}", tag.tag, tag.count);
+/// }
+/// let change = tags.merge(["rustlang", "rust-lang"], "rust").await?;
+/// println!("retagged {} items", change.data.items_affected);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct TagsHandler<'po> {
+    pockety: &'po Pockety,
+    access_token: String,
+    dry_run: bool,
+    chunk_size: usize,
+}
+
+impl<'po> TagsHandler<'po> {
+    pub fn new(pockety: &'po Pockety) -> Self {
+        Self {
+            pockety,
+            access_token: Default::default(),
+            dry_run: false,
+            chunk_size: DEFAULT_CHUNK_SIZE,
+        }
+    }
+
+    pub fn access_token(mut self, access_token: String) -> Self {
+        self.access_token = access_token;
+        self
+    }
+
+    /// Only plan the actions, don't send them
+    pub fn dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+
+    pub fn chunk_size(mut self, chunk_size: usize) -> Self {
+        self.chunk_size = chunk_size.max(1);
+        self
+    }
+
+    /// Every tag in the user's list with how many items have it, most used
+    /// first
+    pub async fn list(&self) -> ApiResult<Vec<TagCount>> {
+        let items = self.items(Query::new()).await?;
+        Ok(PocketyResponse {
+            rate_limits: items.rate_limits,
+            data: tag_counts(&items.data),
+        })
+    }
+
+    /// Renames `old_tag` to `new_tag` on every item that has it. If some
+    /// items already have `new_tag`, the tags are merged item by item instead
+    /// of sending a `tag_rename`, see [`merge_actions`].
+    pub async fn rename(
+        &self,
+        old_tag: impl Into<String>,
+        new_tag: impl Into<String>,
+    ) -> ApiResult<TagChange> {
+        let (old_tag, new_tag) = (old_tag.into(), new_tag.into());
+        let items = self
+            .items(Query::new().any_tag([old_tag.clone(), new_tag.clone()]))
+            .await?;
+        let items_affected = if old_tag == new_tag {
+            0
+        } else {
+            items
+                .data
+                .iter()
+                .filter(|item| item.has_tag(&old_tag))
+                .count()
+        };
+        let actions = if items_affected == 0 {
+            vec![]
+        } else if items.data.iter().any(|item| item.has_tag(&new_tag)) {
+            merge_actions(&items.data, &[old_tag], &new_tag)
+        } else {
+            vec![PocketAction::tag_rename(old_tag, new_tag)]
+        };
+        self.send(items.rate_limits, items_affected, actions).await
+    }
+
+    /// Replaces each of `sources` with `target` on every item that has one of
+    /// them, see [`merge_actions`]
+    pub async fn merge<T>(
+        &self,
+        sources: impl IntoIterator<Item = T>,
+        target: impl Into<String>,
+    ) -> ApiResult<TagChange>
+    where
+        T: Into<String>,
+    {
+        let sources: Vec<String> = sources.into_iter().map(Into::into).collect();
+        let target = target.into();
+        let items = self.items(Query::new().any_tag(sources.clone())).await?;
+        let actions = merge_actions(&items.data, &sources, &target);
+        // items that only have `target` are left alone
+        let items_affected = items
+            .data
+            .iter()
+            .filter(|item| {
+                sources
+                    .iter()
+                    .any(|tag| *tag != target && item.has_tag(tag))
+            })
+            .count();
+        self.send(items.rate_limits, items_affected, actions).await
+    }
+
+    /// Removes `tag` from every item that has it
+    pub async fn delete(&self, tag: impl Into<String>) -> ApiResult<TagChange> {
+        let tag = tag.into();
+        let items = self.items(Query::new().tag(tag.clone())).await?;
+        let actions = if items.data.is_empty() {
+            vec![]
+        } else {
+            vec![PocketAction::tag_delete(tag)]
+        };
+        self.send(items.rate_limits, items.data.len(), actions)
+            .await
+    }
+
+    async fn items(&self, query: Query) -> ApiResult<Vec<PocketItem>> {
+        self.pockety
+            .retrieve()
+            .access_token(self.access_token.clone())
+            .detail_type(DetailType::Complete)
+            .execute_query(&query.state(State::All))
+            .await
+    }
+
+    async fn send(
+        &self,
+        rate_limits: RateLimits,
+        items_affected: usize,
+        actions: Vec<PocketAction>,
+    ) -> ApiResult<TagChange> {
+        if self.dry_run || actions.is_empty() {
+            let outcome = if self.dry_run {
+                BulkOutcome::Planned(actions)
+            } else {
+                BulkOutcome::Sent(ModifyOutcome::default())
+            };
+            return Ok(PocketyResponse {
+                rate_limits,
+                data: TagChange {
+                    items_affected,
+                    outcome,
+                },
+            });
+        }
+
+        self.pockety
+            .modify()
+            .access_token(self.access_token.clone())
+            .chunk_size(self.chunk_size)
+            .extend(actions)
+            .send()
+            .await
+            .map(|res| PocketyResponse {
+                rate_limits: res.rate_limits,
+                data: TagChange {
+                    items_affected,
+                    outcome: BulkOutcome::Sent(res.data),
+                },
+            })
+    }
+}
@@ -0,0 +1,451 @@
+//! Finding items that are saved more than once.
+//!
+//! Pocket only recognises a url it has already saved if it is exactly the same
+//! url, so the same article often ends up in a list several times: once from
+//! a shortened link, once with tracking parameters, once from another mirror.
+//! [`find_duplicates`] groups such items into [`DuplicateCluster`]s, which can
+//! then be merged into a single item with [`merge_actions`].
+//!
+//! Items are compared by `resolved_id`, by their normalized `resolved_url` and
+//! `given_url`, and by near-identical titles. Merging keeps the tags of every
+//! item in a cluster, so items should be retrieved with
+//! `DetailType::Complete`.
+
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+use crate::{
+    api::modify::PocketAction,
+    models::{ItemStatus, PocketItem, Tags},
+    url::{normalize_url, NormalizeOptions, TrailingSlash},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum DuplicateReason {
+    /// Pocket resolved the items to the same `resolved_id`
+    ResolvedId,
+    /// The items' urls are the same once normalized
+    Url,
+    /// The items' titles are the same or nearly so
+    Title,
+}
+
+/// Which item of a cluster is kept when it is merged. Items without a
+/// `time_added` are only kept if no item in the cluster has one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Survivor {
+    /// The item that was saved first
+    Oldest,
+    /// The item that was saved last
+    Newest,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct DedupOptions {
+    pub by_resolved_id: bool,
+    pub by_url: bool,
+    pub by_title: bool,
+    /// How similar two titles must be to count as duplicates, from 0 to 1.
+    /// Titles are compared by the words they share, ignoring case and
+    /// punctuation; 1 only matches titles with exactly the same words.
+    pub title_similarity: f64,
+    /// Titles with fewer words than this are never compared, as short titles
+    /// like "Home" or "Untitled" are shared by unrelated pages
+    pub min_title_words: usize,
+    pub survivor: Survivor,
+}
+
+impl Default for DedupOptions {
+    fn default() -> Self {
+        Self {
+            by_resolved_id: true,
+            by_url: true,
+            by_title: true,
+            title_similarity: 0.9,
+            min_title_words: 3,
+            survivor: Survivor::Oldest,
+        }
+    }
+}
+
+impl DedupOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn by_resolved_id(mut self, by_resolved_id: bool) -> Self {
+        self.by_resolved_id = by_resolved_id;
+        self
+    }
+
+    pub fn by_url(mut self, by_url: bool) -> Self {
+        self.by_url = by_url;
+        self
+    }
+
+    pub fn by_title(mut self, by_title: bool) -> Self {
+        self.by_title = by_title;
+        self
+    }
+
+    pub fn title_similarity(mut self, title_similarity: f64) -> Self {
+        self.title_similarity = title_similarity.clamp(0.0, 1.0);
+        self
+    }
+
+    pub fn min_title_words(mut self, min_title_words: usize) -> Self {
+        self.min_title_words = min_title_words;
+        self
+    }
+
+    pub fn survivor(mut self, survivor: Survivor) -> Self {
+        self.survivor = survivor;
+        self
+    }
+}
+
+/// Items that are saved more than once
+#[derive(Debug, Clone)]
+pub struct DuplicateCluster {
+    /// The items of the cluster, the one to keep first
+    pub items: Vec<PocketItem>,
+    /// Why the items were grouped together
+    pub reasons: BTreeSet<DuplicateReason>,
+}
+
+impl DuplicateCluster {
+    /// The item that is kept when the cluster is merged
+    pub fn survivor(&self) -> &PocketItem {
+        &self.items[0]
+    }
+
+    /// The items that are deleted when the cluster is merged
+    pub fn duplicates(&self) -> &[PocketItem] {
+        &self.items[1..]
+    }
+
+    /// Actions that merge the cluster into its [`survivor`](Self::survivor):
+    /// the survivor gets every tag of its duplicates, and is favorited if any
+    /// of them is, and the duplicates are deleted.
+    pub fn merge_actions(&self) -> Vec<PocketAction> {
+        let survivor = self.survivor();
+        let mut actions = Vec::new();
+
+        let mut tags: Vec<String> = Vec::new();
+        for tag in self.duplicates().iter().flat_map(PocketItem::tag_names) {
+            if !survivor.has_tag(tag) && !tags.contains(tag) {
+                tags.push(tag.clone());
+            }
+        }
+        if !tags.is_empty() {
+            actions.push(PocketAction::tags_add(&survivor.item_id, Tags(tags)));
+        }
+
+        if !survivor.is_favorite() && self.duplicates().iter().any(PocketItem::is_favorite) {
+            actions.push(PocketAction::favorite(&survivor.item_id));
+        }
+
+        actions.extend(
+            self.duplicates()
+                .iter()
+                .map(|item| PocketAction::delete(&item.item_id)),
+        );
+        actions
+    }
+}
+
+/// Groups the items that are saved more than once. Items that aren't
+/// duplicates of anything aren't returned, and neither are deleted items.
+///
+/// Clusters are in the order their first item appears in `items`.
+pub fn find_duplicates(items: &[PocketItem], options: &DedupOptions) -> Vec<DuplicateCluster> {
+    let items: Vec<&PocketItem> = items
+        .iter()
+        .filter(|item| item.status != ItemStatus::Deleted)
+        .collect();
+    let mut clusters = DisjointSet::new(items.len());
+
+    if options.by_resolved_id {
+        let mut seen = HashMap::new();
+        for (index, item) in items.iter().enumerate() {
+            let Some(resolved_id) = item.resolved_id.as_ref().filter(|id| id.0 != "0") else {
+                continue;
+            };
+            if let Some(&other) = seen.get(resolved_id) {
+                clusters.union(other, index, DuplicateReason::ResolvedId);
+            } else {
+                seen.insert(resolved_id, index);
+            }
+        }
+    }
+
+    if options.by_url {
+        let mut seen: HashMap<String, usize> = HashMap::new();
+        for (index, item) in items.iter().enumerate() {
+            let urls = [item.resolved_url.as_deref(), item.given_url.as_deref()];
+            for key in urls.into_iter().flatten().filter_map(url_key) {
+                match seen.get(&key) {
+                    // the resolved and given urls are often the same
+                    Some(&other) if other == index => {}
+                    Some(&other) => clusters.union(other, index, DuplicateReason::Url),
+                    None => {
+                        seen.insert(key, index);
+                    }
+                }
+            }
+        }
+    }
+
+    if options.by_title {
+        let titles: Vec<(usize, HashSet<String>)> = items
+            .iter()
+            .enumerate()
+            .filter_map(|(index, item)| Some((index, title_words(item.title()?))))
+            .filter(|(_, words)| !words.is_empty() && words.len() >= options.min_title_words)
+            .collect();
+
+        for (index, other) in similar_titles(&titles, options.title_similarity) {
+            clusters.union(index, other, DuplicateReason::Title);
+        }
+    }
+
+    // roots are the lowest index of their set, so clusters come out in order
+    let mut groups: Vec<(usize, Vec<usize>)> = Vec::new();
+    let mut positions: HashMap<usize, usize> = HashMap::new();
+    for index in 0..items.len() {
+        let root = clusters.find(index);
+        match positions.get(&root) {
+            Some(&position) => groups[position].1.push(index),
+            None => {
+                positions.insert(root, groups.len());
+                groups.push((root, vec![index]));
+            }
+        }
+    }
+
+    groups
+        .into_iter()
+        .filter(|(_, members)| members.len() > 1)
+        .map(|(root, mut members)| {
+            // `sort_by_key` is stable, so ties keep the order of `items`.
+            // Items without a `time_added` go last either way.
+            match options.survivor {
+                Survivor::Oldest => members.sort_by_key(|&index| {
+                    let time = items[index].time_added;
+                    (time.is_none(), time)
+                }),
+                Survivor::Newest => members.sort_by_key(|&index| {
+                    let time = items[index].time_added;
+                    (time.is_none(), std::cmp::Reverse(time))
+                }),
+            }
+            DuplicateCluster {
+                items: members.iter().map(|&index| items[index].clone()).collect(),
+                reasons: clusters.reasons.remove(&root).unwrap_or_default(),
+            }
+        })
+        .collect()
+}
+
+/// The actions that merge every cluster, see
+/// [`DuplicateCluster::merge_actions`]
+pub fn merge_actions(clusters: &[DuplicateCluster]) -> Vec<PocketAction> {
+    clusters
+        .iter()
+        .flat_map(DuplicateCluster::merge_actions)
+        .collect()
+}
+
+/// A url normalized so that urls of the same page compare equal: without
+/// tracking parameters, fragment, trailing slash, `www.` or scheme
+pub(crate) fn url_key(url: &str) -> Option<String> {
+    let options = NormalizeOptions {
+        strip_fragment: true,
+        trailing_slash: TrailingSlash::Remove,
+        ..Default::default()
+    };
+    let url = normalize_url(url, &options).ok()?;
+    let (_, rest) = url.split_once("://")?;
+    Some(rest.strip_prefix("www.").unwrap_or(rest).to_string())
+}
+
+fn title_words(title: &str) -> HashSet<String> {
+    title
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+/// The pairs of `titles` at least `threshold` similar, by their indexes.
+///
+/// Rather than comparing every pair, each title is only compared with the
+/// earlier ones sharing one of its rarest words: with `n` words, a title can
+/// only be `threshold` similar to another if they share one of its
+/// `n - ceil(threshold * n) + 1` rarest words, so the rest never need to be
+/// looked up.
+fn similar_titles(titles: &[(usize, HashSet<String>)], threshold: f64) -> Vec<(usize, usize)> {
+    // every pair is at least 0 similar
+    if threshold <= 0.0 {
+        return titles
+            .iter()
+            .skip(1)
+            .map(|(index, _)| (titles[0].0, *index))
+            .collect();
+    }
+
+    let mut frequencies: HashMap<&str, usize> = HashMap::new();
+    for word in titles.iter().flat_map(|(_, words)| words) {
+        *frequencies.entry(word).or_default() += 1;
+    }
+
+    let mut similar = Vec::new();
+    let mut by_word: HashMap<&str, Vec<usize>> = HashMap::new();
+    // the last title each earlier title was compared with, so shared words
+    // don't compare the same pair twice
+    let mut compared_with = vec![usize::MAX; titles.len()];
+    for (position, (index, words)) in titles.iter().enumerate() {
+        let mut rarest: Vec<&str> = words.iter().map(String::as_str).collect();
+        rarest.sort_unstable_by_key(|word| (frequencies[word], *word));
+        // a little under, so rounding can't shorten the prefix
+        let overlap = (threshold * words.len() as f64 - 1e-9).ceil() as usize;
+        rarest.truncate(words.len() - overlap.min(words.len()) + 1);
+
+        for word in rarest {
+            let earlier = by_word.entry(word).or_default();
+            for &other in earlier.iter() {
+                if compared_with[other] == position {
+                    continue;
+                }
+                compared_with[other] = position;
+                let (other_index, other_words) = &titles[other];
+                if title_similarity(words, other_words) >= threshold {
+                    similar.push((*other_index, *index));
+                }
+            }
+            earlier.push(position);
+        }
+    }
+    similar
+}
+
+/// The share of words the titles have in common (Jaccard index)
+fn title_similarity(a: &HashSet<String>, b: &HashSet<String>) -> f64 {
+    let shared = a.intersection(b).count();
+    shared as f64 / (a.len() + b.len() - shared) as f64
+}
+
+#[derive(Debug)]
+struct DisjointSet {
+    parents: Vec<usize>,
+    reasons: HashMap<usize, BTreeSet<DuplicateReason>>,
+}
+
+impl DisjointSet {
+    fn new(len: usize) -> Self {
+        Self {
+            parents: (0..len).collect(),
+            reasons: HashMap::new(),
+        }
+    }
+
+    fn find(&mut self, index: usize) -> usize {
+        let mut root = index;
+        while self.parents[root] != root {
+            root = self.parents[root];
+        }
+        let mut index = index;
+        while self.parents[index] != root {
+            index = std::mem::replace(&mut self.parents[index], root);
+        }
+        root
+    }
+
+    /// Joins the sets of `a` and `b`, keeping the lower index as the root
+    fn union(&mut self, a: usize, b: usize, reason: DuplicateReason) {
+        let (a, b) = (self.find(a), self.find(b));
+        let (root, child) = (a.min(b), a.max(b));
+
+        let mut reasons = self.reasons.remove(&child).unwrap_or_default();
+        reasons.insert(reason);
+        self.reasons.entry(root).or_default().extend(reasons);
+        self.parents[child] = root;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn item(value: serde_json::Value) -> PocketItem {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn keeps_items_with_a_save_time_over_those_without() {
+        let items = [
+            item(json!({ "item_id": "1", "status": "0", "given_url": "https://example.com/a" })),
+            item(
+                json!({ "item_id": "2", "status": "0", "given_url": "https://example.com/a", "time_added": "1700000000" }),
+            ),
+            item(
+                json!({ "item_id": "3", "status": "0", "given_url": "https://example.com/a", "time_added": "1600000000" }),
+            ),
+        ];
+        let survivor = |survivor| {
+            let clusters = find_duplicates(&items, &DedupOptions::new().survivor(survivor));
+            assert_eq!(clusters.len(), 1);
+            let ids: Vec<&str> = clusters[0]
+                .items
+                .iter()
+                .map(|item| item.item_id.0.as_str())
+                .collect();
+            ids.join(",")
+        };
+
+        assert_eq!(survivor(Survivor::Oldest), "3,2,1");
+        assert_eq!(survivor(Survivor::Newest), "2,3,1");
+    }
+
+    #[test]
+    fn finds_the_same_similar_titles_as_comparing_every_pair() {
+        let vocabulary = [
+            "rust", "async", "tokio", "guide", "intro", "the", "a", "of", "web", "fast",
+        ];
+        let mut seed: u64 = 42;
+        let mut random = |bound: usize| {
+            seed = seed
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            (seed >> 33) as usize % bound
+        };
+        let titles: Vec<(usize, HashSet<String>)> = (0..300)
+            .map(|index| {
+                let len = 1 + random(6);
+                let words = (0..len).map(|_| vocabulary[random(vocabulary.len())].to_string());
+                (index, words.collect())
+            })
+            .collect();
+
+        for threshold in [0.0, 0.3, 0.5, 0.75, 0.9, 1.0] {
+            let mut expected = Vec::new();
+            for (i, (index, words)) in titles.iter().enumerate() {
+                for (other, other_words) in &titles[i + 1..] {
+                    if title_similarity(words, other_words) >= threshold {
+                        expected.push((*index, *other));
+                    }
+                }
+            }
+
+            let mut found = similar_titles(&titles, threshold);
+            found.sort_unstable();
+            if threshold == 0.0 {
+                // every title is linked to the first instead of to each other
+                assert_eq!(found.len(), titles.len() - 1);
+                continue;
+            }
+            assert_eq!(found, expected, "threshold {threshold}");
+        }
+    }
+}
@@ -0,0 +1,346 @@
+//! Email digests of recently saved and favorited items.
+//!
+//! A [`DigestBuilder`] picks the items saved or favorited in a time window
+//! and groups them by tag or domain into a [`Digest`], which renders as an
+//! html email body and a plain text alternative. Both go through
+//! [`Template`]s rendered over the digest serialized as JSON, so the built-in
+//! [`DEFAULT_HTML`] and [`DEFAULT_TEXT`] can be swapped for custom ones using
+//! the same fields.
+//!
+//! ```
+//! use pockety::digest::{DigestBuilder, GroupBy};
+//!
+//! # let items: Vec<pockety::models::PocketItem> = vec![];
+//! let week_ago = chrono::Utc::now() - chrono::Duration::days(7);
+//! let digest = DigestBuilder::new(week_ago)
+//!     .title("Your week in reading")
+//!     .group_by(GroupBy::Domain)
+//!     .build(&items);
+//! let (html, text) = (digest.html()?, digest.text()?);
+//! # Ok::<(), pockety::Error>(())
+//! ```
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    export::display_title,
+    models::{ItemStatus, PocketItem, Timestamp, DEFAULT_WORDS_PER_MINUTE},
+    template::Template,
+    Error,
+};
+
+/// The group of items without a tag or domain
+pub const OTHER_GROUP: &str = "Other";
+
+pub const DEFAULT_HTML: &str = r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>{{title}}</title>
+</head>
+<body style="margin:0;padding:24px;background:#f4f4f4;font-family:Helvetica,Arial,sans-serif;color:#222">
+<table role="presentation" width="100%" cellpadding="0" cellspacing="0" style="max-width:640px;margin:0 auto;background:#ffffff">
+<tr><td style="padding:24px">
+<h1 style="margin:0 0 4px;font-size:24px">{{title}}</h1>
+<p style="margin:0 0 8px;color:#666666">{{period}} &middot; {{item_label}}{{#reading_time}} &middot; {{reading_time}}{{/reading_time}}</p>
+{{#groups}}
+<h2 style="margin:24px 0 12px;padding-bottom:4px;border-bottom:1px solid #eeeeee;font-size:18px">{{name}} <span style="color:#999999;font-weight:normal;font-size:14px">{{item_count}}{{#reading_time}} &middot; {{reading_time}}{{/reading_time}}</span></h2>
+{{#items}}
+<table role="presentation" width="100%" cellpadding="0" cellspacing="0" style="margin:0 0 16px">
+<tr>
+{{#image}}
+<td width="96" valign="top" style="padding-right:12px"><a href="{{url}}"><img src="{{image}}" width="96" alt="" style="display:block;border:0;border-radius:4px"></a></td>
+{{/image}}
+<td valign="top">
+<a href="{{url}}" style="color:#1a0dab;font-size:16px;font-weight:bold;text-decoration:none">{{title}}</a>{{#favorite}} <span style="color:#e8a400">&#9733;</span>{{/favorite}}
+<div style="margin-top:2px;color:#666666;font-size:13px">{{domain}}{{#reading_time}} &middot; {{reading_time}}{{/reading_time}}</div>
+{{#excerpt}}
+<p style="margin:4px 0 0;font-size:14px;line-height:1.4">{{excerpt}}</p>
+{{/excerpt}}
+</td>
+</tr>
+</table>
+{{/items}}
+{{/groups}}
+{{^groups}}
+<p>Nothing new this time.</p>
+{{/groups}}
+</td></tr>
+</table>
+</body>
+</html>
+"#;
+
+pub const DEFAULT_TEXT: &str = "{{title}}
+{{period}} - {{item_label}}{{#reading_time}}, {{reading_time}}{{/reading_time}}
+{{#groups}}
+
+## {{name}} ({{item_count}}{{#reading_time}}, {{reading_time}}{{/reading_time}})
+{{#items}}
+
+- {{title}}{{#favorite}} *{{/favorite}}
+  {{url}}
+  {{domain}}{{#reading_time}} - {{reading_time}}{{/reading_time}}
+{{/items}}
+{{/groups}}
+{{^groups}}
+
+Nothing new this time.
+{{/groups}}
+";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum GroupBy {
+    /// Items with several tags are grouped under the first alphabetically
+    #[default]
+    Tag,
+    Domain,
+}
+
+#[derive(Debug, Clone)]
+pub struct DigestBuilder {
+    title: String,
+    since: Timestamp,
+    until: Option<Timestamp>,
+    group_by: GroupBy,
+    favorited: bool,
+    words_per_minute: u32,
+}
+
+impl DigestBuilder {
+    /// A digest of the items saved or favorited at or after `since`
+    pub fn new(since: impl Into<Timestamp>) -> Self {
+        Self {
+            title: "Your Pocket digest".to_string(),
+            since: since.into(),
+            until: None,
+            group_by: GroupBy::default(),
+            favorited: true,
+            words_per_minute: DEFAULT_WORDS_PER_MINUTE,
+        }
+    }
+
+    pub fn title(mut self, title: impl Into<String>) -> Self {
+        self.title = title.into();
+        self
+    }
+
+    /// Leaves out items saved or favorited at or after `until`. Defaults to
+    /// no end.
+    pub fn until(mut self, until: impl Into<Timestamp>) -> Self {
+        self.until = Some(until.into());
+        self
+    }
+
+    pub fn group_by(mut self, group_by: GroupBy) -> Self {
+        self.group_by = group_by;
+        self
+    }
+
+    /// Whether to include items saved before the window but favorited in
+    /// it. Defaults to `true`.
+    pub fn favorited(mut self, favorited: bool) -> Self {
+        self.favorited = favorited;
+        self
+    }
+
+    /// Passed to [`PocketItem::reading_minutes`] for the reading times.
+    /// Defaults to [`DEFAULT_WORDS_PER_MINUTE`].
+    pub fn words_per_minute(mut self, words_per_minute: u32) -> Self {
+        self.words_per_minute = words_per_minute.max(1);
+        self
+    }
+
+    pub fn build<'a>(&self, items: impl IntoIterator<Item = &'a PocketItem>) -> Digest {
+        let in_window = |time: Option<Timestamp>| {
+            time.is_some_and(|time| {
+                time >= self.since && self.until.is_none_or(|until| time < until)
+            })
+        };
+
+        let mut groups: BTreeMap<String, Vec<&PocketItem>> = BTreeMap::new();
+        let (mut saved_count, mut favorited_count) = (0, 0);
+        for item in items {
+            if item.status == ItemStatus::Deleted || item.url().is_none() {
+                continue;
+            }
+            let saved = in_window(item.time_added);
+            let favorited = item.is_favorite() && in_window(item.time_favorited);
+            if !(saved || favorited && self.favorited) {
+                continue;
+            }
+            saved_count += usize::from(saved);
+            favorited_count += usize::from(favorited);
+
+            let group = match self.group_by {
+                GroupBy::Tag => item.tag_names().iter().min().cloned(),
+                GroupBy::Domain => item.domain(),
+            };
+            groups
+                .entry(group.unwrap_or_else(|| OTHER_GROUP.to_string()))
+                .or_default()
+                .push(item);
+        }
+
+        let mut groups: Vec<DigestGroup> = groups
+            .into_iter()
+            .map(|(name, mut items)| {
+                items.sort_by(|a, b| {
+                    b.time_added
+                        .cmp(&a.time_added)
+                        .then_with(|| a.item_id.0.cmp(&b.item_id.0))
+                });
+                let entries: Vec<DigestEntry> =
+                    items.into_iter().map(|item| self.entry(item)).collect();
+                let minutes = entries.iter().filter_map(|entry| entry.minutes).sum();
+                DigestGroup {
+                    name,
+                    item_count: entries.len(),
+                    minutes,
+                    reading_time: reading_time(minutes),
+                    items: entries,
+                }
+            })
+            .collect();
+        // largest groups first, and the catch-all group last
+        groups.sort_by(|a, b| {
+            (a.name == OTHER_GROUP)
+                .cmp(&(b.name == OTHER_GROUP))
+                .then_with(|| b.item_count.cmp(&a.item_count))
+                .then_with(|| a.name.cmp(&b.name))
+        });
+
+        let minutes = groups.iter().map(|group| group.minutes).sum();
+        let item_count = groups.iter().map(|group| group.item_count).sum();
+        Digest {
+            title: self.title.clone(),
+            since: self.since,
+            until: self.until,
+            period: period(self.since, self.until),
+            item_count,
+            item_label: item_label(item_count),
+            saved_count,
+            favorited_count,
+            minutes,
+            reading_time: reading_time(minutes),
+            groups,
+        }
+    }
+
+    fn entry(&self, item: &PocketItem) -> DigestEntry {
+        let minutes = item.reading_minutes(self.words_per_minute);
+        DigestEntry {
+            item_id: item.item_id.0.clone(),
+            title: display_title(item).to_string(),
+            url: item.url().unwrap_or_default().to_string(),
+            domain: item.domain(),
+            excerpt: item.excerpt.clone().filter(|excerpt| !excerpt.is_empty()),
+            image: item.top_image_url.clone().filter(|url| !url.is_empty()),
+            tags: item.tag_names().to_vec(),
+            favorite: item.is_favorite(),
+            time_added: item.time_added,
+            minutes,
+            reading_time: minutes.and_then(reading_time),
+        }
+    }
+}
+
+/// The fields templates can use, with `items` nested in `groups`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Digest {
+    pub title: String,
+    pub since: Timestamp,
+    pub until: Option<Timestamp>,
+    /// The window as a date range, e.g. "Nov 7 - Nov 14, 2023"
+    pub period: String,
+    pub item_count: usize,
+    /// `item_count` for display, e.g. "1 item" or "12 items"
+    pub item_label: String,
+    /// Items saved in the window
+    pub saved_count: usize,
+    /// Items favorited in the window
+    pub favorited_count: usize,
+    /// Estimated minutes to read everything
+    pub minutes: u32,
+    /// `minutes` for display, e.g. "1 h 20 min read". `None` if unknown.
+    pub reading_time: Option<String>,
+    pub groups: Vec<DigestGroup>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DigestGroup {
+    /// The tag or domain, or [`OTHER_GROUP`]
+    pub name: String,
+    pub item_count: usize,
+    pub minutes: u32,
+    pub reading_time: Option<String>,
+    pub items: Vec<DigestEntry>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DigestEntry {
+    pub item_id: String,
+    pub title: String,
+    pub url: String,
+    pub domain: Option<String>,
+    pub excerpt: Option<String>,
+    /// The item's `top_image_url`, for a thumbnail
+    pub image: Option<String>,
+    pub tags: Vec<String>,
+    pub favorite: bool,
+    pub time_added: Option<Timestamp>,
+    pub minutes: Option<u32>,
+    pub reading_time: Option<String>,
+}
+
+impl Digest {
+    /// An email subject, e.g. "Your Pocket digest: 12 items"
+    pub fn subject(&self) -> String {
+        format!("{}: {}", self.title, self.item_label)
+    }
+
+    /// Renders the html body with [`DEFAULT_HTML`]
+    pub fn html(&self) -> Result<String, Error> {
+        self.render(&Template::html(DEFAULT_HTML)?)
+    }
+
+    /// Renders the plain text alternative with [`DEFAULT_TEXT`]
+    pub fn text(&self) -> Result<String, Error> {
+        self.render(&Template::text(DEFAULT_TEXT)?)
+    }
+
+    pub fn render(&self, template: &Template) -> Result<String, Error> {
+        Ok(template.render(&serde_json::to_value(self)?))
+    }
+}
+
+fn item_label(count: usize) -> String {
+    match count {
+        1 => "1 item".to_string(),
+        count => format!("{count} items"),
+    }
+}
+
+fn reading_time(minutes: u32) -> Option<String> {
+    match (minutes / 60, minutes % 60) {
+        (0, 0) => None,
+        (0, minutes) => Some(format!("{minutes} min read")),
+        (hours, 0) => Some(format!("{hours} h read")),
+        (hours, minutes) => Some(format!("{hours} h {minutes} min read")),
+    }
+}
+
+fn period(since: Timestamp, until: Option<Timestamp>) -> String {
+    let date = |time: Timestamp| time.to_date_time().unwrap_or_default();
+    let since = date(since);
+    match until.map(date) {
+        Some(until) => format!(
+            "{} - {}",
+            since.format("%b %-d"),
+            until.format("%b %-d, %Y")
+        ),
+        None => format!("Since {}", since.format("%b %-d, %Y")),
+    }
+}
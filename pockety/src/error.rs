@@ -1,6 +1,12 @@
 use std::fmt::{Display, Formatter, Result};
 
-use crate::RateLimits;
+use crate::{
+    api::{
+        batch_add::PartialAdd,
+        modify::{ModifyOutcome, PartialSend},
+    },
+    RateLimits,
+};
 
 #[derive(Debug)]
 pub enum Error {
@@ -8,6 +14,17 @@ pub enum Error {
     Api(ApiError),
     Json(String),
     Parse(String),
+    Io(String),
+    /// Pocket accepted the request but some of its actions failed
+    ActionsFailed(ModifyOutcome),
+    /// A chunk of a chunked request failed after Pocket applied others
+    PartiallySent(PartialSend),
+    /// Like `PartiallySent`, for a [`BatchAddHandler`](crate::api::batch_add::BatchAddHandler)
+    PartiallyAdded(PartialAdd),
+    /// A url was rejected before it was sent to Pocket
+    Url(UrlError),
+    /// The local mirror's database failed
+    Database(String),
 }
 
 impl Display for Error {
@@ -17,6 +34,52 @@ impl Display for Error {
             Error::Api(error) => write!(f, "Api error: {error:?}"),
             Error::Json(error) => write!(f, "Json error: {error:?}"),
             Error::Parse(error) => write!(f, "Parse error: {error:?}"),
+            Error::Io(error) => write!(f, "Io error: {error:?}"),
+            Error::Url(error) => write!(f, "Url error: {error:?}"),
+            Error::Database(error) => write!(f, "Database error: {error:?}"),
+            Error::ActionsFailed(outcome) => write!(
+                f,
+                "Actions failed: {} of {}",
+                outcome.failed().count(),
+                outcome.len()
+            ),
+            Error::PartiallySent(partial) => write!(
+                f,
+                "Partially sent: {} actions applied, {} not: {}",
+                partial.outcome.len(),
+                partial.unsent.len(),
+                partial.error
+            ),
+            Error::PartiallyAdded(partial) => write!(
+                f,
+                "Partially added: {} urls sent, {} not: {}",
+                partial.added.len(),
+                partial.unsent.len(),
+                partial.error
+            ),
+        }
+    }
+}
+
+impl Error {
+    /// Whether the request may succeed if it's sent again later: transport
+    /// failures, rate limiting and server errors. Client errors such as an
+    /// invalid access token are not retryable.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            Error::Http(error) => {
+                error.status.is_server_error()
+                    || error.status == reqwest::StatusCode::TOO_MANY_REQUESTS
+            }
+            Error::PartiallySent(partial) => partial.error.is_retryable(),
+            Error::PartiallyAdded(partial) => partial.error.is_retryable(),
+            Error::Api(_)
+            | Error::Json(_)
+            | Error::Parse(_)
+            | Error::Io(_)
+            | Error::ActionsFailed(_)
+            | Error::Url(_)
+            | Error::Database(_) => false,
         }
     }
 }
@@ -63,6 +126,16 @@ impl HttpError {
     }
 }
 
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UrlError {
+    Empty,
+    /// The url couldn't be parsed
+    Invalid(String),
+    /// Only http and https urls can be saved
+    UnsupportedScheme(String),
+    MissingHost,
+}
+
 #[derive(Debug, Clone, Copy)]
 pub enum ApiError {
     MissingAccessToken,
@@ -80,3 +153,22 @@ impl From<reqwest::header::ToStrError> for Error {
         Error::Json(error.to_string())
     }
 }
+
+impl From<serde_json::Error> for Error {
+    fn from(error: serde_json::Error) -> Self {
+        Error::Json(error.to_string())
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(error: std::io::Error) -> Self {
+        Error::Io(error.to_string())
+    }
+}
+
+#[cfg(feature = "mirror")]
+impl From<rusqlite::Error> for Error {
+    fn from(error: rusqlite::Error) -> Self {
+        Error::Database(error.to_string())
+    }
+}
@@ -0,0 +1,245 @@
+//! Writing a user's list out for backups and migrations.
+//!
+//! Every format keeps each item's url, title, tags, timestamps and whether it
+//! is a favorite and archived. Deleted items are never exported.
+//!
+//! - [`ExportFormat::NetscapeHtml`]: the bookmark file browsers import and
+//!   export, with unread and archived items in separate folders like Pocket's
+//!   own export
+//! - [`ExportFormat::Csv`]: the columns of Pocket's CSV export (`title`, `url`,
+//!   `time_added`, `tags`, `status`) followed by `time_updated`, `time_read`,
+//!   `favorite` and `item_id`
+//! - [`ExportFormat::JsonLines`]: one complete [`PocketItem`] per line
+//! - [`ExportFormat::Markdown`]: a reading list with a checkbox per item
+
+use std::io::Write;
+
+use crate::{
+    models::{ItemStatus, PocketItem, Timestamp},
+    Error,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    NetscapeHtml,
+    Csv,
+    JsonLines,
+    Markdown,
+}
+
+/// Writes `items` to `writer` in `format`, in the order they are given
+pub fn export<'a>(
+    items: impl IntoIterator<Item = &'a PocketItem>,
+    format: ExportFormat,
+    writer: impl Write,
+) -> Result<(), Error> {
+    match format {
+        ExportFormat::NetscapeHtml => write_netscape_html(items, writer),
+        ExportFormat::Csv => write_csv(items, writer),
+        ExportFormat::JsonLines => write_json_lines(items, writer),
+        ExportFormat::Markdown => write_markdown(items, writer),
+    }
+}
+
+/// Like [`export`], but into a string
+pub fn export_to_string<'a>(
+    items: impl IntoIterator<Item = &'a PocketItem>,
+    format: ExportFormat,
+) -> Result<String, Error> {
+    let mut buffer = Vec::new();
+    export(items, format, &mut buffer)?;
+    String::from_utf8(buffer).map_err(|e| Error::Parse(e.to_string()))
+}
+
+pub fn write_netscape_html<'a>(
+    items: impl IntoIterator<Item = &'a PocketItem>,
+    mut writer: impl Write,
+) -> Result<(), Error> {
+    let (unread, archived) = by_status(items);
+
+    writeln!(writer, "<!DOCTYPE NETSCAPE-Bookmark-file-1>")?;
+    writeln!(
+        writer,
+        r#"<META HTTP-EQUIV="Content-Type" CONTENT="text/html; charset=UTF-8">"#
+    )?;
+    writeln!(writer, "<TITLE>Pocket Export</TITLE>")?;
+    writeln!(writer, "<H1>Pocket Export</H1>")?;
+    writeln!(writer, "<DL><p>")?;
+    for (folder, items) in [("Unread", unread), ("Read Archive", archived)] {
+        writeln!(writer, "    <DT><H3>{folder}</H3>")?;
+        writeln!(writer, "    <DL><p>")?;
+        for item in items {
+            write!(
+                writer,
+                r#"        <DT><A HREF="{}""#,
+                escape_html(item.url().unwrap_or_default())
+            )?;
+            if let Some(time) = item.time_added {
+                write!(writer, r#" ADD_DATE="{}""#, time.0)?;
+            }
+            if let Some(time) = item.time_updated {
+                write!(writer, r#" LAST_MODIFIED="{}""#, time.0)?;
+            }
+            if !item.tag_names().is_empty() {
+                write!(
+                    writer,
+                    r#" TAGS="{}""#,
+                    escape_html(&item.tag_names().join(","))
+                )?;
+            }
+            if item.is_favorite() {
+                write!(writer, r#" FAVORITE="1""#)?;
+            }
+            writeln!(writer, ">{}</A>", escape_html(display_title(item)))?;
+        }
+        writeln!(writer, "    </DL><p>")?;
+    }
+    writeln!(writer, "</DL><p>")?;
+    Ok(())
+}
+
+pub fn write_csv<'a>(
+    items: impl IntoIterator<Item = &'a PocketItem>,
+    mut writer: impl Write,
+) -> Result<(), Error> {
+    writeln!(
+        writer,
+        "title,url,time_added,tags,status,time_updated,time_read,favorite,item_id"
+    )?;
+    for item in items
+        .into_iter()
+        .filter(|item| item.status != ItemStatus::Deleted)
+    {
+        let time = |time: Option<Timestamp>| time.map(|time| time.0.to_string());
+        let fields = [
+            item.title().unwrap_or_default().to_string(),
+            item.url().unwrap_or_default().to_string(),
+            time(item.time_added).unwrap_or_default(),
+            item.tag_names().join("|"),
+            match item.status {
+                ItemStatus::Archived => "archive",
+                _ => "unread",
+            }
+            .to_string(),
+            time(item.time_updated).unwrap_or_default(),
+            time(item.time_read).unwrap_or_default(),
+            u8::from(item.is_favorite()).to_string(),
+            item.item_id.0.clone(),
+        ];
+        let fields: Vec<String> = fields.iter().map(|field| escape_csv(field)).collect();
+        writeln!(writer, "{}", fields.join(","))?;
+    }
+    Ok(())
+}
+
+pub fn write_json_lines<'a>(
+    items: impl IntoIterator<Item = &'a PocketItem>,
+    mut writer: impl Write,
+) -> Result<(), Error> {
+    for item in items
+        .into_iter()
+        .filter(|item| item.status != ItemStatus::Deleted)
+    {
+        serde_json::to_writer(&mut writer, item)?;
+        writeln!(writer)?;
+    }
+    Ok(())
+}
+
+pub fn write_markdown<'a>(
+    items: impl IntoIterator<Item = &'a PocketItem>,
+    mut writer: impl Write,
+) -> Result<(), Error> {
+    let (unread, archived) = by_status(items);
+
+    writeln!(writer, "# Reading list")?;
+    for (heading, items) in [("Unread", unread), ("Archive", archived)] {
+        if items.is_empty() {
+            continue;
+        }
+        writeln!(writer)?;
+        writeln!(writer, "## {heading}")?;
+        writeln!(writer)?;
+        for item in items {
+            let done = if item.status == ItemStatus::Archived {
+                "x"
+            } else {
+                " "
+            };
+            write!(
+                writer,
+                "- [{done}] [{}](<{}>)",
+                escape_markdown(display_title(item)),
+                item.url().unwrap_or_default()
+            )?;
+            if item.is_favorite() {
+                write!(writer, " ★")?;
+            }
+            if let Some(date) = item.time_added.and_then(|time| time.to_date_time()) {
+                write!(writer, " — added {}", date.format("%Y-%m-%d"))?;
+            }
+            if !item.tag_names().is_empty() {
+                let tags: Vec<String> = item
+                    .tag_names()
+                    .iter()
+                    .map(|tag| format!("`{tag}`"))
+                    .collect();
+                write!(writer, " — {}", tags.join(" "))?;
+            }
+            writeln!(writer)?;
+        }
+    }
+    Ok(())
+}
+
+/// Splits items into unread and archived ones, dropping deleted ones
+fn by_status<'a>(
+    items: impl IntoIterator<Item = &'a PocketItem>,
+) -> (Vec<&'a PocketItem>, Vec<&'a PocketItem>) {
+    let (archived, unread) = items
+        .into_iter()
+        .filter(|item| item.status != ItemStatus::Deleted)
+        .partition(|item| item.status == ItemStatus::Archived);
+    (unread, archived)
+}
+
+pub(crate) fn display_title(item: &PocketItem) -> &str {
+    item.title()
+        .filter(|title| !title.is_empty())
+        .or(item.url())
+        .unwrap_or_default()
+}
+
+pub(crate) fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn escape_csv(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+fn escape_markdown(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '[' | ']' | '\\' | '*' | '_' | '`') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
@@ -0,0 +1,292 @@
+//! RSS 2.0 and Atom 1.0 feeds of a reading list.
+//!
+//! Output only depends on the items and the feed's settings, never on the
+//! current time or the order the items are given in, so it can be compared
+//! against snapshots: items are sorted newest first by `time_added` and then
+//! by `item_id`, and the feed's own update time is that of its newest item
+//! unless set explicitly.
+//!
+//! ```
+//! use pockety::{feed::Feed, query::Query};
+//!
+//! let feed = Feed::new("Team favorites", "https://example.com/favorites")
+//!     .query(Query::new().favorite(true))
+//!     .limit(20);
+//! let rss = feed.rss(&[]);
+//! assert!(rss.contains("<title>Team favorites</title>"));
+//! ```
+
+use std::fmt::Write;
+
+use crate::{
+    export::{display_title, escape_html},
+    models::{ItemStatus, PocketItem, Timestamp},
+    query::Query,
+};
+
+#[derive(Debug, Clone)]
+pub struct Feed {
+    pub title: String,
+    /// The page the feed is about, e.g. where the reading list is published
+    pub link: String,
+    pub description: Option<String>,
+    /// The Atom feed's author, which entries without authors of their own
+    /// inherit. Defaults to `title`.
+    pub author: Option<String>,
+    /// The Atom feed id. Defaults to `link`.
+    pub id: Option<String>,
+    /// When the feed last changed. Defaults to when its newest item was
+    /// added.
+    pub updated: Option<Timestamp>,
+    /// Only items matching the query are included
+    pub query: Option<Query>,
+    /// At most this many items are included
+    pub limit: Option<usize>,
+}
+
+impl Feed {
+    pub fn new(title: impl Into<String>, link: impl Into<String>) -> Self {
+        Self {
+            title: title.into(),
+            link: link.into(),
+            description: None,
+            author: None,
+            id: None,
+            updated: None,
+            query: None,
+            limit: None,
+        }
+    }
+
+    pub fn description(mut self, description: impl Into<String>) -> Self {
+        self.description = Some(description.into());
+        self
+    }
+
+    pub fn author(mut self, author: impl Into<String>) -> Self {
+        self.author = Some(author.into());
+        self
+    }
+
+    pub fn id(mut self, id: impl Into<String>) -> Self {
+        self.id = Some(id.into());
+        self
+    }
+
+    pub fn updated(mut self, updated: impl Into<Timestamp>) -> Self {
+        self.updated = Some(updated.into());
+        self
+    }
+
+    pub fn query(mut self, query: Query) -> Self {
+        self.query = Some(query);
+        self
+    }
+
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    /// The items the feed includes, in feed order
+    pub fn entries<'a>(
+        &self,
+        items: impl IntoIterator<Item = &'a PocketItem>,
+    ) -> Vec<&'a PocketItem> {
+        let mut entries: Vec<&PocketItem> = items
+            .into_iter()
+            .filter(|item| item.url().is_some())
+            .filter(|item| match &self.query {
+                Some(query) => query.matches(item),
+                None => item.status != ItemStatus::Deleted,
+            })
+            .collect();
+        entries.sort_by(|a, b| {
+            b.time_added
+                .cmp(&a.time_added)
+                .then_with(|| a.item_id.0.cmp(&b.item_id.0))
+        });
+        entries.truncate(self.limit.unwrap_or(usize::MAX));
+        entries
+    }
+
+    fn last_updated(&self, entries: &[&PocketItem]) -> Timestamp {
+        self.updated
+            .or_else(|| entries.iter().filter_map(|item| item.time_added).max())
+            .unwrap_or_default()
+    }
+
+    /// Renders the feed as RSS 2.0
+    pub fn rss<'a>(&self, items: impl IntoIterator<Item = &'a PocketItem>) -> String {
+        let entries = self.entries(items);
+        let mut rss = String::new();
+
+        rss.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        rss.push_str(concat!(
+            "<rss version=\"2.0\"",
+            " xmlns:dc=\"http://purl.org/dc/elements/1.1/\"",
+            " xmlns:media=\"http://search.yahoo.com/mrss/\">\n"
+        ));
+        rss.push_str("<channel>\n");
+        element(&mut rss, 1, "title", &self.title);
+        element(&mut rss, 1, "link", &self.link);
+        element(
+            &mut rss,
+            1,
+            "description",
+            self.description.as_deref().unwrap_or(&self.title),
+        );
+        element(
+            &mut rss,
+            1,
+            "lastBuildDate",
+            &rfc2822(self.last_updated(&entries)),
+        );
+
+        for item in entries {
+            rss.push_str("  <item>\n");
+            element(&mut rss, 2, "title", display_title(item));
+            element(&mut rss, 2, "link", item.url().unwrap_or_default());
+            let _ = writeln!(
+                rss,
+                "    <guid isPermaLink=\"false\">pocket:item:{}</guid>",
+                escape_html(&item.item_id.0)
+            );
+            if let Some(excerpt) = item.excerpt.as_deref().filter(|e| !e.is_empty()) {
+                element(&mut rss, 2, "description", excerpt);
+            }
+            if let Some(time) = item.time_added {
+                element(&mut rss, 2, "pubDate", &rfc2822(time));
+            }
+            for author in authors(item) {
+                element(&mut rss, 2, "dc:creator", author);
+            }
+            for tag in item.tag_names() {
+                element(&mut rss, 2, "category", tag);
+            }
+            if let Some(image) = top_image(item) {
+                let _ = writeln!(rss, "    <media:thumbnail url=\"{}\"/>", escape_html(image));
+            }
+            rss.push_str("  </item>\n");
+        }
+
+        rss.push_str("</channel>\n</rss>\n");
+        rss
+    }
+
+    /// Renders the feed as Atom 1.0
+    pub fn atom<'a>(&self, items: impl IntoIterator<Item = &'a PocketItem>) -> String {
+        let entries = self.entries(items);
+        let mut atom = String::new();
+
+        atom.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        atom.push_str(concat!(
+            "<feed xmlns=\"http://www.w3.org/2005/Atom\"",
+            " xmlns:media=\"http://search.yahoo.com/mrss/\">\n"
+        ));
+        element(&mut atom, 1, "title", &self.title);
+        if let Some(description) = &self.description {
+            element(&mut atom, 1, "subtitle", description);
+        }
+        let _ = writeln!(
+            atom,
+            "  <link rel=\"alternate\" href=\"{}\"/>",
+            escape_html(&self.link)
+        );
+        atom.push_str("  <author>\n");
+        element(
+            &mut atom,
+            2,
+            "name",
+            self.author.as_deref().unwrap_or(&self.title),
+        );
+        atom.push_str("  </author>\n");
+        element(&mut atom, 1, "id", self.id.as_deref().unwrap_or(&self.link));
+        element(
+            &mut atom,
+            1,
+            "updated",
+            &rfc3339(self.last_updated(&entries)),
+        );
+
+        for item in entries {
+            atom.push_str("  <entry>\n");
+            element(&mut atom, 2, "title", display_title(item));
+            let _ = writeln!(
+                atom,
+                "    <link rel=\"alternate\" href=\"{}\"/>",
+                escape_html(item.url().unwrap_or_default())
+            );
+            element(
+                &mut atom,
+                2,
+                "id",
+                &format!("urn:pocket:item:{}", item.item_id.0),
+            );
+            let time = rfc3339(item.time_added.unwrap_or_default());
+            element(&mut atom, 2, "published", &time);
+            element(
+                &mut atom,
+                2,
+                "updated",
+                &item.time_updated.map(rfc3339).unwrap_or(time),
+            );
+            for author in authors(item) {
+                atom.push_str("    <author>\n");
+                element(&mut atom, 3, "name", author);
+                atom.push_str("    </author>\n");
+            }
+            for tag in item.tag_names() {
+                let _ = writeln!(atom, "    <category term=\"{}\"/>", escape_html(tag));
+            }
+            if let Some(excerpt) = item.excerpt.as_deref().filter(|e| !e.is_empty()) {
+                element(&mut atom, 2, "summary", excerpt);
+            }
+            if let Some(image) = top_image(item) {
+                let _ = writeln!(
+                    atom,
+                    "    <media:thumbnail url=\"{}\"/>",
+                    escape_html(image)
+                );
+            }
+            atom.push_str("  </entry>\n");
+        }
+
+        atom.push_str("</feed>\n");
+        atom
+    }
+}
+
+fn element(xml: &mut String, depth: usize, name: &str, text: &str) {
+    let _ = writeln!(
+        xml,
+        "{:indent$}<{name}>{}</{name}>",
+        "",
+        escape_html(text),
+        indent = depth * 2
+    );
+}
+
+fn authors(item: &PocketItem) -> impl Iterator<Item = &str> {
+    item.authors
+        .iter()
+        .flatten()
+        .map(|author| author.name.as_str())
+        .filter(|name| !name.is_empty())
+}
+
+fn top_image(item: &PocketItem) -> Option<&str> {
+    item.top_image_url.as_deref().filter(|url| !url.is_empty())
+}
+
+fn rfc2822(time: Timestamp) -> String {
+    time.to_date_time()
+        .map(|time| time.to_rfc2822())
+        .unwrap_or_default()
+}
+
+fn rfc3339(time: Timestamp) -> String {
+    time.to_date_time()
+        .map(|time| time.to_rfc3339_opts(chrono::SecondsFormat::Secs, true))
+        .unwrap_or_default()
+}
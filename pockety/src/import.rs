@@ -0,0 +1,528 @@
+//! Reading saves exported from Pocket and other services.
+//!
+//! The parsers turn an export into [`ImportedItem`]s, which
+//! [`ImportHandler`](crate::api::import::ImportHandler) adds to a user's list:
+//!
+//! - [`ImportFormat::Html`]: Pocket's `ril_export.html` and Netscape bookmark
+//!   files, as exported by browsers and by [`export`](crate::export). Items
+//!   under a heading or folder with "archive" in its name are archived.
+//! - [`ImportFormat::InstapaperCsv`]: Instapaper's CSV export. The `Archive`
+//!   folder archives items, `Starred` favorites them and any other folder
+//!   becomes a tag.
+//! - [`ImportFormat::PinboardJson`]: Pinboard's JSON export. Bookmarks not
+//!   marked `toread` are archived.
+
+use chrono::DateTime;
+use serde::Deserialize;
+
+use crate::{
+    api::batch_add::NewItem,
+    models::{Tags, Timestamp},
+    Error,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImportFormat {
+    Html,
+    InstapaperCsv,
+    PinboardJson,
+}
+
+/// A save read from an export
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct ImportedItem {
+    pub url: String,
+    pub title: Option<String>,
+    pub tags: Vec<String>,
+    /// When the item was originally saved
+    pub time_added: Option<Timestamp>,
+    pub archived: bool,
+    pub favorite: bool,
+}
+
+impl From<ImportedItem> for NewItem {
+    fn from(item: ImportedItem) -> Self {
+        NewItem {
+            url: item.url,
+            title: item.title,
+            tags: (!item.tags.is_empty()).then_some(Tags(item.tags)),
+            time: item.time_added,
+        }
+    }
+}
+
+pub fn parse(input: &str, format: ImportFormat) -> Result<Vec<ImportedItem>, Error> {
+    match format {
+        ImportFormat::Html => Ok(parse_html(input)),
+        ImportFormat::InstapaperCsv => parse_instapaper_csv(input),
+        ImportFormat::PinboardJson => parse_pinboard_json(input),
+    }
+}
+
+/// Parses Pocket's `ril_export.html` or a Netscape bookmark file. Links
+/// without a `http` or `https` url are skipped.
+pub fn parse_html(input: &str) -> Vec<ImportedItem> {
+    let mut items = Vec::new();
+    let mut archived = false;
+    let mut rest = input;
+
+    while let Some(start) = rest.find('<') {
+        rest = &rest[start + 1..];
+        let Some(end) = rest.find('>') else {
+            break;
+        };
+        let (name, attributes) = split_tag(&rest[..end]);
+        rest = &rest[end + 1..];
+
+        match name.as_str() {
+            "h1" | "h2" | "h3" => {
+                let (text, after) = element_text(rest, &name);
+                archived = text.to_lowercase().contains("archive");
+                rest = after;
+            }
+            "a" => {
+                let (text, after) = element_text(rest, "a");
+                rest = after;
+
+                let attribute = |name: &str| {
+                    attributes
+                        .iter()
+                        .find(|(key, _)| key == name)
+                        .map(|(_, value)| value.as_str())
+                };
+                let Some(url) = attribute("href").map(str::trim) else {
+                    continue;
+                };
+                if !(url.starts_with("http://") || url.starts_with("https://")) {
+                    continue;
+                }
+
+                let time_added = attribute("time_added")
+                    .or(attribute("add_date"))
+                    .and_then(|time| time.trim().parse().ok())
+                    .map(Timestamp);
+                let tags = attribute("tags")
+                    .map(|tags| split_tags(tags, ','))
+                    .unwrap_or_default();
+                let text = text.trim();
+
+                items.push(ImportedItem {
+                    url: url.to_string(),
+                    title: (!text.is_empty() && text != url).then(|| text.to_string()),
+                    tags,
+                    time_added,
+                    archived,
+                    favorite: attribute("favorite").is_some_and(|favorite| favorite == "1"),
+                });
+            }
+            _ => {}
+        }
+    }
+
+    items
+}
+
+/// Parses Instapaper's CSV export, with the columns `URL`, `Title`,
+/// `Selection`, `Folder`, `Timestamp` and, in newer exports, `Tags`
+pub fn parse_instapaper_csv(input: &str) -> Result<Vec<ImportedItem>, Error> {
+    let mut rows = parse_csv(input).into_iter();
+    let Some(header) = rows.next() else {
+        return Ok(vec![]);
+    };
+    let column = |name: &str| {
+        header
+            .iter()
+            .position(|column| column.trim().eq_ignore_ascii_case(name))
+    };
+    let url_column = column("url")
+        .ok_or_else(|| Error::Parse("Instapaper CSV has no URL column".to_string()))?;
+    let (title, folder, timestamp, tags) = (
+        column("title"),
+        column("folder"),
+        column("timestamp"),
+        column("tags"),
+    );
+
+    let mut items = Vec::new();
+    for row in rows {
+        let field = |index: Option<usize>| {
+            index
+                .and_then(|index| row.get(index))
+                .map(|field| field.trim())
+                .filter(|field| !field.is_empty())
+        };
+        let Some(url) = field(Some(url_column)) else {
+            continue;
+        };
+
+        // tags are exported as a JSON list
+        let mut item_tags: Vec<String> = field(tags)
+            .and_then(|tags| serde_json::from_str(tags).ok())
+            .unwrap_or_default();
+        let folder = field(folder).unwrap_or("Unread");
+        let (archived, favorite) = match folder {
+            "Archive" => (true, false),
+            "Starred" => (false, true),
+            "Unread" => (false, false),
+            folder => {
+                item_tags.push(folder.to_string());
+                (false, false)
+            }
+        };
+
+        items.push(ImportedItem {
+            url: url.to_string(),
+            title: field(title).map(str::to_string),
+            tags: item_tags,
+            time_added: field(timestamp)
+                .and_then(|time| time.parse().ok())
+                .map(Timestamp),
+            archived,
+            favorite,
+        });
+    }
+    Ok(items)
+}
+
+#[derive(Deserialize)]
+struct PinboardBookmark {
+    href: String,
+    #[serde(default)]
+    description: String,
+    #[serde(default)]
+    tags: String,
+    time: Option<String>,
+    #[serde(default)]
+    toread: String,
+}
+
+/// Parses Pinboard's JSON export
+pub fn parse_pinboard_json(input: &str) -> Result<Vec<ImportedItem>, Error> {
+    let bookmarks: Vec<PinboardBookmark> = serde_json::from_str(input)?;
+
+    Ok(bookmarks
+        .into_iter()
+        .map(|bookmark| {
+            let title = bookmark.description.trim();
+            ImportedItem {
+                title: (!title.is_empty()).then(|| title.to_string()),
+                url: bookmark.href,
+                tags: split_tags(&bookmark.tags, ' '),
+                time_added: bookmark
+                    .time
+                    .and_then(|time| DateTime::parse_from_rfc3339(&time).ok())
+                    .map(|time| Timestamp(time.timestamp())),
+                archived: bookmark.toread != "yes",
+                favorite: false,
+            }
+        })
+        .collect())
+}
+
+fn split_tags(tags: &str, separator: char) -> Vec<String> {
+    tags.split(separator)
+        .map(str::trim)
+        .filter(|tag| !tag.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Splits the inside of an html tag into its lowercase name and attributes
+fn split_tag(tag: &str) -> (String, Vec<(String, String)>) {
+    let tag = tag.trim().trim_end_matches('/');
+    let name_end = tag.find(char::is_whitespace).unwrap_or(tag.len());
+    let name = tag[..name_end].to_lowercase();
+
+    let mut attributes = Vec::new();
+    let mut rest = tag[name_end..].trim_start();
+    while !rest.is_empty() {
+        let key_end = rest
+            .find(|c: char| c == '=' || c.is_whitespace())
+            .unwrap_or(rest.len());
+        let key = rest[..key_end].to_lowercase();
+        rest = rest[key_end..].trim_start();
+
+        let mut value = String::new();
+        if let Some(after) = rest.strip_prefix('=') {
+            let after = after.trim_start();
+            let (raw, remaining) = match after.chars().next() {
+                Some(quote @ ('"' | '\'')) => {
+                    let after = &after[1..];
+                    let end = after.find(quote).unwrap_or(after.len());
+                    (&after[..end], after.get(end + 1..).unwrap_or_default())
+                }
+                _ => {
+                    let end = after.find(char::is_whitespace).unwrap_or(after.len());
+                    (&after[..end], &after[end..])
+                }
+            };
+            value = unescape_html(raw);
+            rest = remaining.trim_start();
+        }
+        if !key.is_empty() {
+            attributes.push((key, value));
+        }
+    }
+
+    (name, attributes)
+}
+
+/// The text of an element up to its closing tag, without any tags inside it,
+/// and the input after the closing tag
+fn element_text<'a>(input: &'a str, name: &str) -> (String, &'a str) {
+    let closing = format!("</{name}");
+    let lowercase = input.to_ascii_lowercase();
+    let end = lowercase.find(&closing).unwrap_or(input.len());
+    let after = input[end..]
+        .find('>')
+        .map(|close| &input[end + close + 1..])
+        .unwrap_or_default();
+
+    let mut text = String::new();
+    let mut in_tag = false;
+    for c in input[..end].chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' => in_tag = false,
+            c if !in_tag => text.push(c),
+            _ => {}
+        }
+    }
+    (unescape_html(&text), after)
+}
+
+fn unescape_html(text: &str) -> String {
+    let mut unescaped = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(start) = rest.find('&') {
+        unescaped.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        let entity = rest[1..]
+            .find(';')
+            .filter(|end| *end <= 10)
+            .map(|end| &rest[1..end + 1]);
+        let decoded = entity.and_then(|entity| match entity {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            "nbsp" => Some('\u{a0}'),
+            _ => {
+                let code = entity.strip_prefix('#')?;
+                let code = match code.strip_prefix(['x', 'X']) {
+                    Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+                    None => code.parse().ok()?,
+                };
+                char::from_u32(code)
+            }
+        });
+
+        match (entity, decoded) {
+            (Some(entity), Some(c)) => {
+                unescaped.push(c);
+                rest = &rest[entity.len() + 2..];
+            }
+            _ => {
+                unescaped.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    unescaped.push_str(rest);
+    unescaped
+}
+
+/// Splits CSV into rows of fields, following RFC 4180
+fn parse_csv(input: &str) -> Vec<Vec<String>> {
+    let mut rows = Vec::new();
+    let mut row = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = input.trim_start_matches('\u{feff}').chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match (c, quoted) {
+            ('"', true) if chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            ('"', true) => quoted = false,
+            ('"', false) if field.is_empty() => quoted = true,
+            (',', false) => row.push(std::mem::take(&mut field)),
+            ('\r', false) => {}
+            ('\n', false) => {
+                row.push(std::mem::take(&mut field));
+                rows.push(std::mem::take(&mut row));
+            }
+            (c, _) => field.push(c),
+        }
+    }
+    if !field.is_empty() || !row.is_empty() {
+        row.push(field);
+        rows.push(row);
+    }
+
+    rows.retain(|row| row.iter().any(|field| !field.is_empty()));
+    rows
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::{
+        export::{export_to_string, ExportFormat},
+        models::PocketItem,
+    };
+
+    fn item(value: serde_json::Value) -> PocketItem {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn parses_pocket_html_export() {
+        let html = r#"<!DOCTYPE html>
+<html><body>
+<h1>Unread</h1>
+<ul>
+<li><a href="https://example.com/a?x=1&amp;y=2" time_added="1700000000" tags="rust,async">Fish &amp; chips &#8212; &#x41;</a></li>
+<li><a href="javascript:alert(1)">Not a save</a></li>
+</ul>
+<h1>Read Archive</h1>
+<ul>
+<li><A HREF='https://example.com/b' TIME_ADDED=1600000000 FAVORITE="1">https://example.com/b</A></li>
+</ul>
+</body></html>"#;
+
+        assert_eq!(
+            parse_html(html),
+            vec![
+                ImportedItem {
+                    url: "https://example.com/a?x=1&y=2".to_string(),
+                    title: Some("Fish & chips \u{2014} A".to_string()),
+                    tags: vec!["rust".to_string(), "async".to_string()],
+                    time_added: Some(Timestamp(1_700_000_000)),
+                    archived: false,
+                    favorite: false,
+                },
+                ImportedItem {
+                    url: "https://example.com/b".to_string(),
+                    title: None,
+                    tags: vec![],
+                    time_added: Some(Timestamp(1_600_000_000)),
+                    archived: true,
+                    favorite: true,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn leaves_unknown_and_unterminated_entities_alone() {
+        assert_eq!(
+            unescape_html("a &bogus; b & c &#xZZ; &amp"),
+            "a &bogus; b & c &#xZZ; &amp"
+        );
+        assert_eq!(unescape_html("&lt;&gt;&quot;&apos;&#39;"), "<>\"''");
+    }
+
+    #[test]
+    fn parses_quoted_csv_fields() {
+        let csv = "\u{feff}a,b,c\r\n\"1, one\",\"say \"\"hi\"\"\",\"two\nlines\"\r\n\r\n,,\nlast,,";
+
+        assert_eq!(
+            parse_csv(csv),
+            vec![
+                vec!["a", "b", "c"],
+                vec!["1, one", "say \"hi\"", "two\nlines"],
+                vec!["last", "", ""],
+            ]
+        );
+    }
+
+    #[test]
+    fn parses_instapaper_folders_and_tags() {
+        let csv = "URL,Title,Selection,Folder,Timestamp,Tags
+https://example.com/a,A,,Archive,1700000000,\"[\"\"rust\"\"]\"
+https://example.com/b,B,,Starred,,
+https://example.com/c,,,Recipes,,
+,No url,,Unread,,
+";
+        let items = parse_instapaper_csv(csv).unwrap();
+
+        assert_eq!(items.len(), 3);
+        assert!(items[0].archived && !items[0].favorite);
+        assert_eq!(items[0].tags, vec!["rust"]);
+        assert_eq!(items[0].time_added, Some(Timestamp(1_700_000_000)));
+        assert!(items[1].favorite && !items[1].archived);
+        assert_eq!(items[2].title, None);
+        assert_eq!(items[2].tags, vec!["Recipes"]);
+    }
+
+    #[test]
+    fn reimports_an_html_export() {
+        let items = [
+            item(json!({
+                "item_id": "1",
+                "given_url": "https://example.com/a?x=1&y=2",
+                "given_title": "Commas, \"quotes\" & <tags>",
+                "status": "0",
+                "time_added": "1700000000",
+                "tags": { "rust": {}, "async": {} },
+            })),
+            item(json!({
+                "item_id": "2",
+                "given_url": "https://example.com/b",
+                "given_title": "Read",
+                "status": "1",
+                "favorite": "1",
+            })),
+        ];
+        let html = export_to_string(&items, ExportFormat::NetscapeHtml).unwrap();
+
+        assert_eq!(
+            parse_html(&html),
+            vec![
+                ImportedItem {
+                    url: "https://example.com/a?x=1&y=2".to_string(),
+                    title: Some("Commas, \"quotes\" & <tags>".to_string()),
+                    tags: vec!["async".to_string(), "rust".to_string()],
+                    time_added: Some(Timestamp(1_700_000_000)),
+                    archived: false,
+                    favorite: false,
+                },
+                ImportedItem {
+                    url: "https://example.com/b".to_string(),
+                    title: Some("Read".to_string()),
+                    tags: vec![],
+                    time_added: None,
+                    archived: true,
+                    favorite: true,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn reimports_a_csv_export_with_quoted_commas_and_newlines() {
+        let items = [item(json!({
+            "item_id": "1",
+            "given_url": "https://example.com/a?x=1,2",
+            "given_title": "Commas, \"quotes\"\nand a second line",
+            "status": "0",
+        }))];
+        let csv = export_to_string(&items, ExportFormat::Csv).unwrap();
+
+        let imported = parse_instapaper_csv(&csv).unwrap();
+        assert_eq!(imported.len(), 1);
+        assert_eq!(imported[0].url, "https://example.com/a?x=1,2");
+        assert_eq!(
+            imported[0].title.as_deref(),
+            Some("Commas, \"quotes\"\nand a second line")
+        );
+    }
+}
@@ -11,14 +11,32 @@
 
 use std::str::FromStr;
 
-use api::{add::AddHandler, modify::ModifyHandler, retrieve::RetrieveHandler};
+use api::{
+    add::AddHandler, batch_add::BatchAddHandler, bulk::BulkHandler, import::ImportHandler,
+    item::ItemHandle, modify::ModifyHandler, retrieve::RetrieveHandler, tags::TagsHandler,
+};
 use futures::TryFutureExt;
+use models::PocketItem;
 use reqwest::{header::HeaderMap, Client};
 use serde::{self, de::DeserializeOwned, Deserialize, Serialize};
+pub mod analytics;
 pub mod api;
+pub mod dedup;
+pub mod digest;
 mod error;
-pub use error::{ApiError, Error, HttpError};
+pub mod export;
+pub mod feed;
+pub mod import;
+#[cfg(feature = "mirror")]
+pub mod mirror;
+pub use error::{ApiError, Error, HttpError, UrlError};
 pub mod models;
+pub mod prioritize;
+pub mod query;
+#[cfg(feature = "rules")]
+pub mod rules;
+pub mod template;
+pub mod url;
 pub use reqwest;
 
 #[derive(Serialize, Debug, Clone)]
@@ -199,15 +217,36 @@ impl Pockety {
         .await
     }
 
-    pub fn retrieve(&self) -> RetrieveHandler {
+    pub fn retrieve(&self) -> RetrieveHandler<'_> {
         RetrieveHandler::new(self)
     }
 
-    pub fn modify(&self) -> ModifyHandler {
+    pub fn modify(&self) -> ModifyHandler<'_> {
         ModifyHandler::new(self)
     }
 
-    pub fn add(&self) -> AddHandler {
+    pub fn add(&self) -> AddHandler<'_> {
         AddHandler::new(self)
     }
+
+    pub fn batch_add(&self) -> BatchAddHandler<'_> {
+        BatchAddHandler::new(self)
+    }
+
+    pub fn bulk(&self) -> BulkHandler<'_> {
+        BulkHandler::new(self)
+    }
+
+    pub fn import(&self) -> ImportHandler<'_> {
+        ImportHandler::new(self)
+    }
+
+    pub fn tags(&self) -> TagsHandler<'_> {
+        TagsHandler::new(self)
+    }
+
+    /// Wraps a retrieved item so that actions can be sent on it directly
+    pub fn item(&self, access_token: impl Into<String>, item: PocketItem) -> ItemHandle<'_> {
+        ItemHandle::new(self, access_token, item)
+    }
 }
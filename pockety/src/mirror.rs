@@ -0,0 +1,563 @@
+//! A local SQLite copy of a user's list.
+//!
+//! A [`Mirror`] is filled by [`Mirror::sync`], which only fetches what changed
+//! since the previous sync, and can then be queried without contacting Pocket.
+//! Items are stored whole, along with tables of their tags, authors and
+//! images, and indexed by status, tag, domain and time. Their titles,
+//! excerpts, urls, tags and authors are also indexed for full-text search
+//! with [`Mirror::search`].
+
+use std::path::Path;
+
+use rusqlite::{params, types::Value, Connection, OptionalExtension, Transaction};
+
+use crate::{
+    api::tags::TagCount,
+    models::{DetailType, ItemId, ItemStatus, PocketItem, Sort, State, Timestamp},
+    query::Query,
+    ApiResult, Error, Pockety, PocketyResponse,
+};
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS items (
+    item_id TEXT PRIMARY KEY,
+    resolved_id TEXT,
+    status INTEGER NOT NULL,
+    favorite INTEGER NOT NULL,
+    url TEXT,
+    title TEXT,
+    domain TEXT,
+    word_count INTEGER,
+    time_added INTEGER,
+    time_updated INTEGER,
+    time_read INTEGER,
+    time_favorited INTEGER,
+    item TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS items_status ON items (status);
+CREATE INDEX IF NOT EXISTS items_domain ON items (domain);
+CREATE INDEX IF NOT EXISTS items_time_added ON items (time_added);
+CREATE INDEX IF NOT EXISTS items_time_updated ON items (time_updated);
+
+CREATE TABLE IF NOT EXISTS tags (
+    item_id TEXT NOT NULL REFERENCES items (item_id) ON DELETE CASCADE,
+    tag TEXT NOT NULL,
+    PRIMARY KEY (item_id, tag)
+);
+CREATE INDEX IF NOT EXISTS tags_tag ON tags (tag);
+
+CREATE TABLE IF NOT EXISTS authors (
+    item_id TEXT NOT NULL REFERENCES items (item_id) ON DELETE CASCADE,
+    author_id TEXT NOT NULL,
+    name TEXT NOT NULL,
+    url TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS authors_item_id ON authors (item_id);
+
+CREATE TABLE IF NOT EXISTS images (
+    item_id TEXT NOT NULL REFERENCES items (item_id) ON DELETE CASCADE,
+    image_id TEXT NOT NULL,
+    src TEXT NOT NULL,
+    width TEXT NOT NULL,
+    height TEXT NOT NULL,
+    caption TEXT NOT NULL,
+    credit TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS images_item_id ON images (item_id);
+
+CREATE VIRTUAL TABLE IF NOT EXISTS items_fts USING fts5 (
+    item_id UNINDEXED,
+    title,
+    excerpt,
+    url,
+    tags,
+    authors,
+    tokenize = 'unicode61 remove_diacritics 2'
+);
+
+CREATE TABLE IF NOT EXISTS meta (
+    key TEXT PRIMARY KEY,
+    value TEXT NOT NULL
+);
+";
+
+/// What a [`Mirror::sync`] changed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SyncReport {
+    /// Whether the whole list was fetched, rather than only what changed
+    pub full: bool,
+    /// Items that were added or changed
+    pub updated: usize,
+    /// Items that were deleted
+    pub removed: usize,
+    /// The point in time the mirror is now current as of
+    pub since: Option<Timestamp>,
+}
+
+/// An item found by [`Mirror::search`]
+#[derive(Debug, Clone)]
+pub struct SearchHit {
+    pub item: PocketItem,
+    /// How well the item matches, higher is better. Only comparable between
+    /// hits of the same search.
+    pub score: f64,
+    /// The part of the item that matches best, with the matching words in
+    /// `[brackets]`
+    pub snippet: Option<String>,
+}
+
+#[derive(Debug)]
+pub struct Mirror {
+    connection: Connection,
+}
+
+impl Mirror {
+    /// Opens the mirror stored at `path`, creating it if needed
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, Error> {
+        Self::new(Connection::open(path)?)
+    }
+
+    /// A mirror that only lives as long as the value, e.g. for tests
+    pub fn open_in_memory() -> Result<Self, Error> {
+        Self::new(Connection::open_in_memory()?)
+    }
+
+    fn new(connection: Connection) -> Result<Self, Error> {
+        connection.execute_batch("PRAGMA foreign_keys = ON;")?;
+        connection.execute_batch(SCHEMA)?;
+        Ok(Self { connection })
+    }
+
+    /// The `since` of the last sync, `None` if the mirror was never synced
+    pub fn last_sync(&self) -> Result<Option<Timestamp>, Error> {
+        let since: Option<String> = self
+            .connection
+            .query_row("SELECT value FROM meta WHERE key = 'since'", [], |row| {
+                row.get(0)
+            })
+            .optional()?;
+        Ok(since.and_then(|since| since.parse().ok()).map(Timestamp))
+    }
+
+    /// Brings the mirror up to date. The first sync fetches the whole list,
+    /// later ones only the items that changed since the previous sync.
+    pub async fn sync(
+        &mut self,
+        pockety: &Pockety,
+        access_token: impl Into<String>,
+    ) -> ApiResult<SyncReport> {
+        let last_sync = self.last_sync()?;
+
+        let mut handler = pockety
+            .retrieve()
+            .access_token(access_token.into())
+            .state(State::All)
+            .detail_type(DetailType::Complete);
+        if let Some(last_sync) = last_sync {
+            handler = handler.since(last_sync);
+        }
+        let pages = handler.execute_pages(|_| true).await?;
+
+        let mut report = self.apply(&pages.data.items, pages.data.since)?;
+        report.full = last_sync.is_none();
+        Ok(PocketyResponse {
+            rate_limits: pages.rate_limits,
+            data: report,
+        })
+    }
+
+    /// Stores items retrieved with `DetailType::Complete` and `State::All`,
+    /// removing the ones Pocket reports as deleted, and records `since` as the
+    /// point the mirror is current as of. [`sync`](Self::sync) does this for
+    /// you; this is for items retrieved some other way.
+    pub fn apply(
+        &mut self,
+        items: &[PocketItem],
+        since: Option<Timestamp>,
+    ) -> Result<SyncReport, Error> {
+        let transaction = self.connection.transaction()?;
+        let mut report = SyncReport {
+            full: false,
+            updated: 0,
+            removed: 0,
+            since,
+        };
+
+        for item in items {
+            if item.status == ItemStatus::Deleted {
+                transaction.execute(
+                    "DELETE FROM items_fts WHERE item_id = ?1",
+                    [&item.item_id.0],
+                )?;
+                report.removed += transaction
+                    .execute("DELETE FROM items WHERE item_id = ?1", [&item.item_id.0])?;
+            } else {
+                upsert(&transaction, item)?;
+                report.updated += 1;
+            }
+        }
+        if let Some(since) = since {
+            transaction.execute(
+                "INSERT INTO meta (key, value) VALUES ('since', ?1)
+                 ON CONFLICT (key) DO UPDATE SET value = excluded.value",
+                [since.0.to_string()],
+            )?;
+        }
+
+        transaction.commit()?;
+        Ok(report)
+    }
+
+    /// Removes every item and forgets the last sync, so the next sync fetches
+    /// the whole list again
+    pub fn clear(&mut self) -> Result<(), Error> {
+        self.connection
+            .execute_batch("DELETE FROM items; DELETE FROM items_fts; DELETE FROM meta;")?;
+        Ok(())
+    }
+
+    pub fn len(&self) -> Result<usize, Error> {
+        let len: i64 = self
+            .connection
+            .query_row("SELECT COUNT(*) FROM items", [], |row| row.get(0))?;
+        Ok(len as usize)
+    }
+
+    pub fn is_empty(&self) -> Result<bool, Error> {
+        Ok(self.len()? == 0)
+    }
+
+    pub fn get(&self, item_id: impl Into<ItemId>) -> Result<Option<PocketItem>, Error> {
+        let item: Option<String> = self
+            .connection
+            .query_row(
+                "SELECT item FROM items WHERE item_id = ?1",
+                [item_id.into().0],
+                |row| row.get(0),
+            )
+            .optional()?;
+        Ok(item.map(|item| serde_json::from_str(&item)).transpose()?)
+    }
+
+    /// Every item in the mirror, newest first
+    pub fn items(&self) -> Result<Vec<PocketItem>, Error> {
+        self.query(&Query::new())
+    }
+
+    /// The items matching `query`, sorted by its `sort` (newest first by
+    /// default). Everything but `search` is supported, see
+    /// [`search`](Self::search) for that; the indexed predicates are
+    /// evaluated by SQLite and the rest with [`Query::matches`].
+    pub fn query(&self, query: &Query) -> Result<Vec<PocketItem>, Error> {
+        let (conditions, values) = sql_conditions(query);
+        let order = match query.sort {
+            Some(Sort::Newest) | None => "time_added DESC, item_id",
+            Some(Sort::Oldest) => "time_added ASC, item_id",
+            Some(Sort::Title) => "title COLLATE NOCASE, item_id",
+            Some(Sort::Site) => "domain, item_id",
+        };
+        let sql = format!(
+            "SELECT item FROM items WHERE {} ORDER BY {order}",
+            conditions.join(" AND ")
+        );
+
+        let mut statement = self.connection.prepare(&sql)?;
+        let rows = statement.query_map(rusqlite::params_from_iter(values), |row| {
+            row.get::<_, String>(0)
+        })?;
+
+        let mut items = Vec::new();
+        for row in rows {
+            let item: PocketItem = serde_json::from_str(&row?)?;
+            if query.matches(&item) {
+                items.push(item);
+            }
+        }
+        Ok(items)
+    }
+
+    /// Searches the titles, excerpts, urls, tags and author names of the
+    /// items for `text`, best matches first. Only items that also match
+    /// `query` are returned, so e.g. `Query::new().tag("rust")` limits the
+    /// search to items tagged `rust`; the query's own `search` and `sort` are
+    /// ignored.
+    ///
+    /// Every word of `text` must match, ignoring case and diacritics. Words in
+    /// double quotes must match as a phrase, and a word ending in `*` matches
+    /// any word it is a prefix of: `"rust async" tokio*`. Matches in titles
+    /// rank highest, then tags, authors, excerpts and urls.
+    pub fn search(&self, text: &str, query: &Query) -> Result<Vec<SearchHit>, Error> {
+        let Some(text) = fts_query(text) else {
+            return Ok(vec![]);
+        };
+
+        let (conditions, mut values) = sql_conditions(query);
+        values.insert(0, Value::from(text));
+        let sql = format!(
+            "SELECT items.item,
+                bm25(items_fts, 0.0, 10.0, 2.0, 1.0, 5.0, 3.0) AS rank,
+                snippet(items_fts, -1, '[', ']', '…', 12)
+            FROM items_fts JOIN items ON items.item_id = items_fts.item_id
+            WHERE items_fts MATCH ? AND {}
+            ORDER BY rank, items.item_id",
+            conditions.join(" AND ")
+        );
+
+        let mut statement = self.connection.prepare(&sql)?;
+        let rows = statement.query_map(rusqlite::params_from_iter(values), |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, f64>(1)?,
+                row.get::<_, Option<String>>(2)?,
+            ))
+        })?;
+
+        let mut hits = Vec::new();
+        for row in rows {
+            let (item, rank, snippet) = row?;
+            let item: PocketItem = serde_json::from_str(&item)?;
+            if query.matches(&item) {
+                hits.push(SearchHit {
+                    item,
+                    score: -rank,
+                    snippet: snippet.filter(|snippet| !snippet.is_empty()),
+                });
+            }
+        }
+        Ok(hits)
+    }
+
+    /// Rebuilds the search index from the stored items
+    pub fn rebuild_search_index(&mut self) -> Result<(), Error> {
+        let transaction = self.connection.transaction()?;
+        transaction.execute("DELETE FROM items_fts", [])?;
+        {
+            let mut statement = transaction.prepare("SELECT item FROM items")?;
+            let rows = statement.query_map([], |row| row.get::<_, String>(0))?;
+            for row in rows {
+                index(&transaction, &serde_json::from_str(&row?)?)?;
+            }
+        }
+        transaction.commit()?;
+        Ok(())
+    }
+
+    /// Every tag in the mirror with how many items have it, most used first
+    pub fn tags(&self) -> Result<Vec<TagCount>, Error> {
+        let mut statement = self.connection.prepare(
+            "SELECT tag, COUNT(*) AS count FROM tags GROUP BY tag ORDER BY count DESC, tag",
+        )?;
+        let tags = statement
+            .query_map([], |row| {
+                Ok(TagCount {
+                    tag: row.get(0)?,
+                    count: row.get::<_, i64>(1)? as usize,
+                })
+            })?
+            .collect::<Result<_, _>>()?;
+        Ok(tags)
+    }
+
+    /// The underlying SQLite connection, for queries the mirror doesn't offer
+    pub fn connection(&self) -> &Connection {
+        &self.connection
+    }
+}
+
+fn upsert(transaction: &Transaction<'_>, item: &PocketItem) -> Result<(), Error> {
+    let item_id = &item.item_id.0;
+    transaction.execute(
+        "INSERT INTO items (
+            item_id, resolved_id, status, favorite, url, title, domain, word_count,
+            time_added, time_updated, time_read, time_favorited, item
+        ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)
+        ON CONFLICT (item_id) DO UPDATE SET
+            resolved_id = excluded.resolved_id,
+            status = excluded.status,
+            favorite = excluded.favorite,
+            url = excluded.url,
+            title = excluded.title,
+            domain = excluded.domain,
+            word_count = excluded.word_count,
+            time_added = excluded.time_added,
+            time_updated = excluded.time_updated,
+            time_read = excluded.time_read,
+            time_favorited = excluded.time_favorited,
+            item = excluded.item",
+        params![
+            item_id,
+            item.resolved_id.as_ref().map(|id| &id.0),
+            item.status.as_u8(),
+            item.is_favorite(),
+            item.url(),
+            item.title(),
+            item.domain(),
+            item.words(),
+            item.time_added.map(|time| time.0),
+            item.time_updated.map(|time| time.0),
+            item.time_read.map(|time| time.0),
+            item.time_favorited.map(|time| time.0),
+            serde_json::to_string(item)?,
+        ],
+    )?;
+
+    transaction.execute("DELETE FROM items_fts WHERE item_id = ?1", [item_id])?;
+    index(transaction, item)?;
+
+    for table in ["tags", "authors", "images"] {
+        transaction.execute(
+            &format!("DELETE FROM {table} WHERE item_id = ?1"),
+            [item_id],
+        )?;
+    }
+    for tag in item.tag_names() {
+        transaction.execute(
+            "INSERT OR IGNORE INTO tags (item_id, tag) VALUES (?1, ?2)",
+            params![item_id, tag],
+        )?;
+    }
+    for author in item.authors.iter().flatten() {
+        transaction.execute(
+            "INSERT INTO authors (item_id, author_id, name, url) VALUES (?1, ?2, ?3, ?4)",
+            params![item_id, author.id.0, author.name, author.url],
+        )?;
+    }
+    for image in item.images.iter().flatten() {
+        transaction.execute(
+            "INSERT INTO images (item_id, image_id, src, width, height, caption, credit)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![
+                item_id,
+                image.image_id.0,
+                image.src,
+                image.width,
+                image.height,
+                image.caption,
+                image.credit
+            ],
+        )?;
+    }
+    Ok(())
+}
+
+/// Adds an item to the search index
+fn index(transaction: &Transaction<'_>, item: &PocketItem) -> Result<(), Error> {
+    let authors: Vec<&str> = item
+        .authors
+        .iter()
+        .flatten()
+        .map(|author| author.name.as_str())
+        .collect();
+    transaction.execute(
+        "INSERT INTO items_fts (item_id, title, excerpt, url, tags, authors)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        params![
+            item.item_id.0,
+            item.title(),
+            item.excerpt,
+            item.url(),
+            item.tag_names().join(" "),
+            authors.join(" "),
+        ],
+    )?;
+    Ok(())
+}
+
+/// Turns search text into an FTS5 query, quoting every word and phrase so
+/// that FTS5 operators and punctuation in the text are matched literally
+fn fts_query(text: &str) -> Option<String> {
+    let quote = |term: &str| format!("\"{}\"", term.replace('"', "\"\""));
+    let mut terms = Vec::new();
+
+    for (index, part) in text.split('"').enumerate() {
+        // odd parts are between quotes
+        if index % 2 == 1 {
+            if !part.trim().is_empty() {
+                terms.push(quote(part.trim()));
+            }
+            continue;
+        }
+        for word in part.split_whitespace() {
+            match word.strip_suffix('*') {
+                Some(prefix) if !prefix.is_empty() => terms.push(format!("{}*", quote(prefix))),
+                Some(_) => {}
+                None => terms.push(quote(word)),
+            }
+        }
+    }
+
+    (!terms.is_empty()).then(|| terms.join(" "))
+}
+
+/// Escapes the wildcards of a `LIKE` pattern, for use with `ESCAPE '\'`
+fn escape_like(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '\\' | '%' | '_') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// The predicates of `query` that can use the mirror's indexes
+fn sql_conditions(query: &Query) -> (Vec<String>, Vec<Value>) {
+    let mut conditions = vec!["1 = 1".to_string()];
+    let mut values = Vec::new();
+    let has_tag = "EXISTS (SELECT 1 FROM tags WHERE tags.item_id = items.item_id AND tags.tag";
+
+    match query.state {
+        Some(State::Unread) => conditions.push("status = 0".to_string()),
+        Some(State::Archive) => conditions.push("status = 1".to_string()),
+        Some(State::All) | None => {}
+    }
+    if let Some(favorite) = query.favorite {
+        conditions.push("favorite = ?".to_string());
+        values.push(Value::from(favorite));
+    }
+    if query.untagged {
+        conditions
+            .push("NOT EXISTS (SELECT 1 FROM tags WHERE tags.item_id = items.item_id)".into());
+    }
+    for tag in &query.all_tags {
+        conditions.push(format!("{has_tag} = ?)"));
+        values.push(Value::from(tag.clone()));
+    }
+    if !query.any_tags.is_empty() {
+        let placeholders = vec!["?"; query.any_tags.len()].join(", ");
+        conditions.push(format!("{has_tag} IN ({placeholders}))"));
+        values.extend(query.any_tags.iter().cloned().map(Value::from));
+    }
+    if !query.domains.is_empty() {
+        let domains =
+            vec![r"(domain = ? OR domain LIKE ? ESCAPE '\')"; query.domains.len()].join(" OR ");
+        conditions.push(format!("({domains})"));
+        for domain in &query.domains {
+            values.push(Value::from(domain.clone()));
+            values.push(Value::from(format!("%.{}", escape_like(domain))));
+        }
+    }
+    if let Some(min) = query.min_word_count {
+        conditions.push("word_count >= ?".to_string());
+        values.push(Value::from(min));
+    }
+    if let Some(max) = query.max_word_count {
+        conditions.push("word_count <= ?".to_string());
+        values.push(Value::from(max));
+    }
+    if let Some(after) = query.added_after {
+        conditions.push("time_added >= ?".to_string());
+        values.push(Value::from(after.0));
+    }
+    if let Some(before) = query.added_before {
+        conditions.push("time_added < ?".to_string());
+        values.push(Value::from(before.0));
+    }
+    if let Some(since) = query.updated_since {
+        conditions.push("time_updated >= ?".to_string());
+        values.push(Value::from(since.0));
+    }
+
+    (conditions, values)
+}
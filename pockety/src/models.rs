@@ -1,13 +1,39 @@
-use chrono::{DateTime, Utc};
+use std::{
+    collections::BTreeMap,
+    fmt::{self, Display, Formatter},
+};
+
+use chrono::{DateTime, TimeZone, Utc};
 use serde::{de, Deserialize, Serialize};
 
-#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+/// Seconds since the unix epoch, the way Pocket encodes times. Convert it to a
+/// `DateTime` with [`to_date_time`](Self::to_date_time).
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
 pub struct Timestamp(pub i64);
 
 impl Timestamp {
     pub fn now() -> Self {
         Self(Utc::now().timestamp())
     }
+
+    /// Pocket uses `0` to mean "never", e.g. for `time_read` on an unread item
+    pub fn is_zero(&self) -> bool {
+        self.0 == 0
+    }
+
+    /// Returns `None` for Pocket's zero timestamp
+    pub fn non_zero(self) -> Option<Self> {
+        if self.is_zero() {
+            None
+        } else {
+            Some(self)
+        }
+    }
+
+    /// Returns `None` if the timestamp is out of range for `DateTime<Utc>`
+    pub fn to_date_time(&self) -> Option<DateTime<Utc>> {
+        Utc.timestamp_opt(self.0, 0).single()
+    }
 }
 
 impl From<DateTime<Utc>> for Timestamp {
@@ -16,13 +42,53 @@ impl From<DateTime<Utc>> for Timestamp {
     }
 }
 
+impl From<i64> for Timestamp {
+    fn from(seconds: i64) -> Self {
+        Timestamp(seconds)
+    }
+}
+
+impl Display for Timestamp {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self.to_date_time() {
+            Some(date_time) => write!(f, "{}", date_time.to_rfc3339()),
+            None => write!(f, "{}", self.0),
+        }
+    }
+}
+
 impl<'de> Deserialize<'de> for Timestamp {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where
         D: serde::Deserializer<'de>,
     {
-        String::deserialize(deserializer)
-            .and_then(|op| op.parse::<i64>().map(Timestamp).map_err(de::Error::custom))
+        struct TimestampVisitor;
+
+        impl de::Visitor<'_> for TimestampVisitor {
+            type Value = Timestamp;
+
+            fn expecting(&self, f: &mut Formatter<'_>) -> fmt::Result {
+                f.write_str("a unix timestamp as an integer or a string")
+            }
+
+            fn visit_i64<E: de::Error>(self, value: i64) -> Result<Self::Value, E> {
+                Ok(Timestamp(value))
+            }
+
+            fn visit_u64<E: de::Error>(self, value: u64) -> Result<Self::Value, E> {
+                i64::try_from(value).map(Timestamp).map_err(E::custom)
+            }
+
+            fn visit_str<E: de::Error>(self, value: &str) -> Result<Self::Value, E> {
+                value
+                    .trim()
+                    .parse::<i64>()
+                    .map(Timestamp)
+                    .map_err(E::custom)
+            }
+        }
+
+        deserializer.deserialize_any(TimestampVisitor)
     }
 }
 
@@ -35,6 +101,18 @@ impl Serialize for Timestamp {
     }
 }
 
+/// Deserializes an optional timestamp, treating Pocket's `0` ("never") as
+/// `None`.
+pub fn deserialize_non_zero_timestamp<'de, D>(
+    deserializer: D,
+) -> Result<Option<Timestamp>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    Option::<Timestamp>::deserialize(deserializer)
+        .map(|timestamp| timestamp.and_then(Timestamp::non_zero))
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Tags(pub Vec<String>);
 
@@ -43,8 +121,26 @@ impl<'de> Deserialize<'de> for Tags {
     where
         D: serde::Deserializer<'de>,
     {
-        let tags = Vec::<String>::deserialize(deserializer)?;
-        Ok(Self(tags))
+        // Items retrieved with `detailType=complete` encode their tags as an
+        // object keyed by tag name rather than as a list, and requests take
+        // them comma separated
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum ListOrMap {
+            List(Vec<String>),
+            Map(BTreeMap<String, serde_json::Value>),
+            CommaSeparated(String),
+        }
+
+        match ListOrMap::deserialize(deserializer)? {
+            ListOrMap::List(tags) => Ok(Self(tags)),
+            ListOrMap::Map(tags) => Ok(Self(tags.into_keys().collect())),
+            ListOrMap::CommaSeparated(tags) => Ok(tags
+                .split(',')
+                .map(str::trim)
+                .filter(|tag| !tag.is_empty())
+                .collect()),
+        }
     }
 }
 
@@ -57,7 +153,45 @@ impl Serialize for Tags {
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+impl Tags {
+    pub fn contains(&self, tag: &str) -> bool {
+        self.0.iter().any(|t| t == tag)
+    }
+}
+
+/// A single tag
+impl From<&str> for Tags {
+    fn from(tag: &str) -> Self {
+        Self(vec![tag.to_string()])
+    }
+}
+
+/// A single tag
+impl From<String> for Tags {
+    fn from(tag: String) -> Self {
+        Self(vec![tag])
+    }
+}
+
+impl<T: Into<String>> From<Vec<T>> for Tags {
+    fn from(tags: Vec<T>) -> Self {
+        tags.into_iter().collect()
+    }
+}
+
+impl<T: Into<String>, const N: usize> From<[T; N]> for Tags {
+    fn from(tags: [T; N]) -> Self {
+        tags.into_iter().collect()
+    }
+}
+
+impl<T: Into<String>> FromIterator<T> for Tags {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        Self(iter.into_iter().map(Into::into).collect())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct ItemId(pub String);
 
 impl<'de> Deserialize<'de> for ItemId {
@@ -65,8 +199,41 @@ impl<'de> Deserialize<'de> for ItemId {
     where
         D: serde::Deserializer<'de>,
     {
-        let id = String::deserialize(deserializer)?;
-        Ok(Self(id))
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum StringOrNumber {
+            String(String),
+            Number(u64),
+        }
+
+        match StringOrNumber::deserialize(deserializer)? {
+            StringOrNumber::String(id) => Ok(Self(id)),
+            StringOrNumber::Number(id) => Ok(Self(id.to_string())),
+        }
+    }
+}
+
+impl From<String> for ItemId {
+    fn from(id: String) -> Self {
+        Self(id)
+    }
+}
+
+impl From<&str> for ItemId {
+    fn from(id: &str) -> Self {
+        Self(id.to_string())
+    }
+}
+
+impl From<&ItemId> for ItemId {
+    fn from(id: &ItemId) -> Self {
+        id.clone()
+    }
+}
+
+impl Display for ItemId {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
     }
 }
 
@@ -88,6 +255,9 @@ pub struct ItemImage {
     pub height: String,
     pub caption: String,
     pub credit: String,
+    /// Any fields Pocket sends that aren't modelled above
+    #[serde(flatten, deserialize_with = "deserialize_extra")]
+    pub extra: serde_json::Map<String, serde_json::Value>,
 }
 
 #[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
@@ -99,13 +269,25 @@ pub struct ItemVideo {
     pub height: String,
     pub length: Option<String>,
     pub vid: String,
+    /// The video host, e.g. `"1"` for YouTube
+    #[serde(rename = "type")]
+    pub video_type: Option<String>,
+    /// Any fields Pocket sends that aren't modelled above
+    #[serde(flatten, deserialize_with = "deserialize_extra")]
+    pub extra: serde_json::Map<String, serde_json::Value>,
 }
 
 #[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
 pub struct ItemAuthor {
+    #[serde(alias = "author_id")]
     pub id: ItemId,
+    /// The item the author wrote, sent with complete details
+    pub item_id: Option<ItemId>,
     pub name: String,
     pub url: String,
+    /// Any fields Pocket sends that aren't modelled above
+    #[serde(flatten, deserialize_with = "deserialize_extra")]
+    pub extra: serde_json::Map<String, serde_json::Value>,
 }
 
 #[derive(Serialize, Deserialize, Debug, PartialEq, Clone, Copy)]
@@ -208,23 +390,56 @@ impl AsRef<str> for State {
     }
 }
 
-#[derive(Serialize, Deserialize, Debug, Copy, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Tag {
-    #[serde(rename = "_untagged_")]
+    /// Only items without any tags
     Untagged,
-    #[serde(rename = "tag_name")]
-    TagName,
+    /// Only items tagged with the given tag
+    TagName(String),
+}
+
+impl Tag {
+    pub const UNTAGGED: &str = "_untagged_";
 }
 
 impl AsRef<str> for Tag {
     fn as_ref(&self) -> &str {
         match self {
-            Tag::Untagged => "_untagged_",
-            Tag::TagName => "tag_name",
+            Tag::Untagged => Self::UNTAGGED,
+            Tag::TagName(name) => name,
+        }
+    }
+}
+
+impl<T: Into<String>> From<T> for Tag {
+    fn from(name: T) -> Self {
+        let name = name.into();
+        if name == Self::UNTAGGED {
+            Tag::Untagged
+        } else {
+            Tag::TagName(name)
         }
     }
 }
 
+impl<'de> Deserialize<'de> for Tag {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        String::deserialize(deserializer).map(Tag::from)
+    }
+}
+
+impl Serialize for Tag {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        self.as_ref().serialize(serializer)
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug, Copy, Clone)]
 pub enum ContentType {
     #[serde(rename = "article")]
@@ -267,13 +482,17 @@ pub struct PocketItem {
     pub favorite: Option<String>,
     /// 0, 1, 2 - 1 if the item is archived - 2 if the item should be deleted
     pub status: ItemStatus,
-    // TODO: add description
+    /// When the item was saved
+    #[serde(default, deserialize_with = "deserialize_non_zero_timestamp")]
     pub time_added: Option<Timestamp>,
-    // TODO: add description
+    /// When the item was last changed
+    #[serde(default, deserialize_with = "deserialize_non_zero_timestamp")]
     pub time_updated: Option<Timestamp>,
-    // TODO: add description
+    /// When the item was archived, `None` if it hasn't been read
+    #[serde(default, deserialize_with = "deserialize_non_zero_timestamp")]
     pub time_read: Option<Timestamp>,
-    // TODO: add description
+    /// When the item was favorited, `None` if it isn't a favorite
+    #[serde(default, deserialize_with = "deserialize_non_zero_timestamp")]
     pub time_favorited: Option<Timestamp>,
     // TODO: add description
     pub sort_id: Option<u32>,
@@ -295,12 +514,15 @@ pub struct PocketItem {
     /// How many words are in the article
     pub word_count: Option<String>,
     /// A JSON object of the user tags associated with the item
-    pub tags: Option<String>,
+    pub tags: Option<Tags>,
     /// A JSON object listing all of the authors associated with the item
+    #[serde(default, deserialize_with = "deserialize_map_values")]
     pub authors: Option<Vec<ItemAuthor>>,
     /// A JSON object listing all of the images associated with the item
+    #[serde(default, deserialize_with = "deserialize_map_values")]
     pub images: Option<Vec<ItemImage>>,
     /// A JSON object listing all of the videos associated with the item
+    #[serde(default, deserialize_with = "deserialize_map_values")]
     pub videos: Option<Vec<ItemVideo>>,
     // TODO: add description
     pub lang: Option<String>,
@@ -310,6 +532,242 @@ pub struct PocketItem {
     pub listen_duration_estimate: Option<u32>,
     // TODO: add description
     pub top_image_url: Option<String>,
-    // TODO: add description
-    pub domain_metadata: Option<serde_json::Value>,
+    /// Metadata about the domain the item was saved from
+    pub domain_metadata: Option<DomainMetadata>,
+    /// Any fields Pocket sends that aren't modelled above, so that they
+    /// survive a round trip
+    #[serde(flatten, deserialize_with = "deserialize_extra")]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone, Default)]
+pub struct DomainMetadata {
+    /// The display name of the domain, e.g. "The Verge"
+    pub name: Option<String>,
+    /// Url of the domain's logo
+    pub logo: Option<String>,
+    /// Url of a greyscale version of the domain's logo
+    pub greyscale_logo: Option<String>,
+    /// Any fields Pocket sends that aren't modelled above
+    #[serde(flatten, deserialize_with = "deserialize_extra")]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone, Default)]
+pub struct SearchMeta {
+    /// The kind of search Pocket performed, e.g. "normal"
+    pub search_type: Option<String>,
+    /// Total number of items matching the search, regardless of `count` and
+    /// `offset`
+    #[serde(default, deserialize_with = "deserialize_optional_u32")]
+    pub total_result_count: Option<u32>,
+    /// Any fields Pocket sends that aren't modelled above
+    #[serde(flatten, deserialize_with = "deserialize_extra")]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+/// The reading speed used for items Pocket has no `time_to_read` for, see
+/// [`PocketItem::reading_minutes`]
+pub const DEFAULT_WORDS_PER_MINUTE: u32 = 220;
+
+/// Seconds in a day, for measuring ages in days from [`Timestamp`]s
+pub(crate) const DAY: i64 = 24 * 60 * 60;
+
+impl PocketItem {
+    /// 1 if the item is favorited
+    pub fn is_favorite(&self) -> bool {
+        self.favorite.as_deref() == Some("1")
+    }
+
+    /// 1 if the item is an article
+    pub fn is_article(&self) -> bool {
+        self.is_article.as_deref() == Some("1")
+    }
+
+    /// The url to show the user: the resolved url if Pocket has one, otherwise
+    /// the url that was saved
+    pub fn url(&self) -> Option<&str> {
+        self.resolved_url
+            .as_deref()
+            .filter(|url| !url.is_empty())
+            .or(self.given_url.as_deref())
+    }
+
+    /// The title to show the user: the resolved title if Pocket found one,
+    /// otherwise the title that was saved
+    pub fn title(&self) -> Option<&str> {
+        self.resolved_title
+            .as_deref()
+            .filter(|title| !title.is_empty())
+            .or(self.given_title.as_deref())
+    }
+
+    /// The host of [`url`](Self::url), without a leading `www.`
+    pub fn domain(&self) -> Option<String> {
+        let url = reqwest::Url::parse(self.url()?).ok()?;
+        let host = url.host_str()?;
+        Some(host.strip_prefix("www.").unwrap_or(host).to_lowercase())
+    }
+
+    /// `word_count` as a number
+    pub fn words(&self) -> Option<u32> {
+        self.word_count.as_deref()?.parse().ok()
+    }
+
+    /// Minutes the item takes to read: Pocket's `time_to_read` if it has one,
+    /// otherwise `word_count` at `words_per_minute`, usually
+    /// [`DEFAULT_WORDS_PER_MINUTE`]
+    pub fn reading_minutes(&self, words_per_minute: u32) -> Option<u32> {
+        self.time_to_read
+            .filter(|minutes| *minutes > 0)
+            .or_else(|| Some(self.words()?.div_ceil(words_per_minute.max(1))))
+    }
+
+    /// The item's tags. Only populated for items retrieved with
+    /// `DetailType::Complete`.
+    pub fn tag_names(&self) -> &[String] {
+        self.tags
+            .as_ref()
+            .map(|tags| tags.0.as_slice())
+            .unwrap_or_default()
+    }
+
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tag_names().iter().any(|t| t == tag)
+    }
+}
+
+/// Collects the fields of a response that aren't modelled by its struct. With
+/// the `strict` feature enabled any such field is an error instead, which is
+/// useful for contract tests against the live API.
+pub(crate) fn deserialize_extra<'de, D>(
+    deserializer: D,
+) -> Result<serde_json::Map<String, serde_json::Value>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let extra = serde_json::Map::deserialize(deserializer)?;
+
+    #[cfg(feature = "strict")]
+    if let Some(field) = extra.keys().next() {
+        return Err(de::Error::custom(format!("unknown field `{field}`")));
+    }
+
+    Ok(extra)
+}
+
+/// Items retrieved with `detailType=complete` encode their authors, images and
+/// videos as objects keyed by id rather than as lists, so accept both.
+pub(crate) fn deserialize_map_values<'de, D, T>(deserializer: D) -> Result<Option<Vec<T>>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum ListOrMap<T> {
+        List(Vec<T>),
+        Map(BTreeMap<String, T>),
+    }
+
+    Ok(
+        Option::<ListOrMap<T>>::deserialize(deserializer)?.map(|values| match values {
+            ListOrMap::List(values) => values,
+            ListOrMap::Map(values) => values.into_values().collect(),
+        }),
+    )
+}
+
+/// Pocket isn't consistent about encoding numbers, so accept both `12` and
+/// `"12"`.
+pub(crate) fn deserialize_optional_u32<'de, D>(deserializer: D) -> Result<Option<u32>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum NumberOrString {
+        Number(u32),
+        String(String),
+    }
+
+    match Option::<NumberOrString>::deserialize(deserializer)? {
+        Some(NumberOrString::Number(number)) => Ok(Some(number)),
+        Some(NumberOrString::String(string)) if string.is_empty() => Ok(None),
+        Some(NumberOrString::String(string)) => string.parse().map(Some).map_err(de::Error::custom),
+        None => Ok(None),
+    }
+}
+
+/// Accepts `0`/`1` as numbers or strings as well as booleans
+pub(crate) fn deserialize_optional_bool<'de, D>(deserializer: D) -> Result<Option<bool>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Flag {
+        Bool(bool),
+        Number(u8),
+        String(String),
+    }
+
+    match Option::<Flag>::deserialize(deserializer)? {
+        Some(Flag::Bool(flag)) => Ok(Some(flag)),
+        Some(Flag::Number(flag)) => Ok(Some(flag != 0)),
+        Some(Flag::String(flag)) => match flag.as_str() {
+            "" => Ok(None),
+            "0" | "false" => Ok(Some(false)),
+            "1" | "true" => Ok(Some(true)),
+            flag => Err(de::Error::invalid_value(
+                de::Unexpected::Str(flag),
+                &"0 or 1",
+            )),
+        },
+        None => Ok(None),
+    }
+}
+
+/// Accepts unix timestamps as well as the `2012-10-03 23:04:56` (UTC) format
+/// `/v3/add` uses for dates. Zero dates, e.g. `0000-00-00 00:00:00` for an
+/// unknown publishing date, are `None`.
+pub(crate) fn deserialize_date_time<'de, D>(deserializer: D) -> Result<Option<Timestamp>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum DateTimeOrTimestamp {
+        Timestamp(Timestamp),
+        DateTime(String),
+    }
+
+    match Option::<DateTimeOrTimestamp>::deserialize(deserializer)? {
+        Some(DateTimeOrTimestamp::Timestamp(timestamp)) => Ok(timestamp.non_zero()),
+        Some(DateTimeOrTimestamp::DateTime(date_time)) => {
+            if date_time.is_empty() || date_time.starts_with("0000-00-00") {
+                return Ok(None);
+            }
+            chrono::NaiveDateTime::parse_from_str(&date_time, "%Y-%m-%d %H:%M:%S")
+                .map(|date_time| Some(Timestamp::from(date_time.and_utc())))
+                .map_err(de::Error::custom)
+        }
+        None => Ok(None),
+    }
+}
+
+/// Pocket takes tags in requests as a comma separated string. Works for both
+/// `Tags` and `Option<Tags>` fields.
+pub(crate) fn serialize_comma_separated<'a, T, S>(
+    tags: &'a T,
+    serializer: S,
+) -> Result<S::Ok, S::Error>
+where
+    &'a T: Into<Option<&'a Tags>>,
+    S: serde::Serializer,
+{
+    match tags.into() {
+        Some(tags) => tags.0.join(",").serialize(serializer),
+        None => serializer.serialize_none(),
+    }
 }
@@ -0,0 +1,224 @@
+//! Suggesting what to read next.
+//!
+//! A [`Prioritizer`] scores unread items as the sum of weighted factors:
+//!
+//! - fit: 1 for items that can be read within the time budget, falling off
+//!   for longer ones, and 0.5 for items without a known reading time
+//! - age: from 0 for items saved today towards 1 for old ones. A negative
+//!   weight prefers fresh items instead.
+//! - domains, tags and content types: the weight of each one the item has
+//!
+//! ```
+//! use pockety::{models::ContentType, prioritize::{fit, Prioritizer}};
+//!
+//! # let items: Vec<pockety::models::PocketItem> = vec![];
+//! let queue = Prioritizer::new()
+//!     .budget(20)
+//!     .domain("lwn.net", 2.0)
+//!     .tag("rust", 1.0)
+//!     .content_type(ContentType::Video, -1.0)
+//!     .rank(&items);
+//! for next in fit(queue, 45) {
+//!     println!("{:.2} {:?}", next.score, next.item.title());
+//! }
+//! ```
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    models::{ContentType, ItemStatus, PocketItem, Timestamp, DAY, DEFAULT_WORDS_PER_MINUTE},
+    query::Query,
+};
+
+#[derive(Debug, Clone)]
+pub struct Prioritizer {
+    /// Minutes available to read. Without a budget fit doesn't count.
+    pub budget: Option<u32>,
+    /// Passed to [`PocketItem::reading_minutes`] to estimate fit. Defaults to
+    /// [`DEFAULT_WORDS_PER_MINUTE`].
+    pub words_per_minute: u32,
+    /// Defaults to 1
+    pub fit_weight: f64,
+    /// Defaults to 0.5
+    pub age_weight: f64,
+    /// Items this many days old score 0.5 for age. Defaults to 30.
+    pub age_half_days: u32,
+    /// Matches subdomains too
+    pub domains: Vec<(String, f64)>,
+    pub tags: Vec<(String, f64)>,
+    pub content_types: Vec<(ContentType, f64)>,
+    /// The time ages are measured from. Defaults to the current time.
+    pub now: Option<Timestamp>,
+}
+
+impl Default for Prioritizer {
+    fn default() -> Self {
+        Self {
+            budget: None,
+            words_per_minute: DEFAULT_WORDS_PER_MINUTE,
+            fit_weight: 1.0,
+            age_weight: 0.5,
+            age_half_days: 30,
+            domains: vec![],
+            tags: vec![],
+            content_types: vec![],
+            now: None,
+        }
+    }
+}
+
+/// How much each factor added to an item's score
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub struct Factors {
+    pub fit: f64,
+    pub age: f64,
+    pub domain: f64,
+    pub tags: f64,
+    pub content_type: f64,
+}
+
+impl Factors {
+    pub fn total(&self) -> f64 {
+        self.fit + self.age + self.domain + self.tags + self.content_type
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Scored {
+    pub item: PocketItem,
+    pub score: f64,
+    pub factors: Factors,
+    /// Estimated reading time, if known
+    pub minutes: Option<u32>,
+}
+
+impl Prioritizer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn budget(mut self, minutes: u32) -> Self {
+        self.budget = Some(minutes);
+        self
+    }
+
+    pub fn words_per_minute(mut self, words_per_minute: u32) -> Self {
+        self.words_per_minute = words_per_minute.max(1);
+        self
+    }
+
+    pub fn fit_weight(mut self, weight: f64) -> Self {
+        self.fit_weight = weight;
+        self
+    }
+
+    pub fn age_weight(mut self, weight: f64) -> Self {
+        self.age_weight = weight;
+        self
+    }
+
+    pub fn age_half_days(mut self, days: u32) -> Self {
+        self.age_half_days = days.max(1);
+        self
+    }
+
+    pub fn domain(mut self, domain: impl Into<String>, weight: f64) -> Self {
+        self.domains.push((domain.into(), weight));
+        self
+    }
+
+    pub fn tag(mut self, tag: impl Into<String>, weight: f64) -> Self {
+        self.tags.push((tag.into(), weight));
+        self
+    }
+
+    pub fn content_type(mut self, content_type: ContentType, weight: f64) -> Self {
+        self.content_types.push((content_type, weight));
+        self
+    }
+
+    pub fn now(mut self, now: impl Into<Timestamp>) -> Self {
+        self.now = Some(now.into());
+        self
+    }
+
+    /// Scores a single item, whatever its state
+    pub fn score(&self, item: &PocketItem) -> Scored {
+        let now = self.now.unwrap_or_else(Timestamp::now);
+        self.score_at(item, now)
+    }
+
+    /// The unread items, highest score first. Ties go to the item saved
+    /// first.
+    pub fn rank<'a>(&self, items: impl IntoIterator<Item = &'a PocketItem>) -> Vec<Scored> {
+        let now = self.now.unwrap_or_else(Timestamp::now);
+        let mut queue: Vec<Scored> = items
+            .into_iter()
+            .filter(|item| item.status == ItemStatus::Normal)
+            .map(|item| self.score_at(item, now))
+            .collect();
+        queue.sort_by(|a, b| {
+            b.score
+                .total_cmp(&a.score)
+                .then_with(|| a.item.time_added.cmp(&b.item.time_added))
+                .then_with(|| a.item.item_id.0.cmp(&b.item.item_id.0))
+        });
+        queue
+    }
+
+    fn score_at(&self, item: &PocketItem, now: Timestamp) -> Scored {
+        let minutes = item.reading_minutes(self.words_per_minute);
+
+        let fit = match (self.budget, minutes) {
+            (None, _) => 0.0,
+            (Some(_), None) => 0.5,
+            (Some(budget), Some(minutes)) if minutes <= budget => 1.0,
+            (Some(budget), Some(minutes)) => f64::from(budget) / f64::from(minutes),
+        };
+        let age = item.time_added.map_or(0.0, |added| {
+            let days = (now.0 - added.0).max(0) as f64 / DAY as f64;
+            days / (days + f64::from(self.age_half_days))
+        });
+        let weigh = |weights: &[(String, f64)], query: fn(&str) -> Query| -> f64 {
+            weights
+                .iter()
+                .filter(|(name, _)| query(name).matches(item))
+                .fold(0.0, |sum, (_, weight)| sum + weight)
+        };
+
+        let factors = Factors {
+            fit: self.fit_weight * fit,
+            age: self.age_weight * age,
+            domain: weigh(&self.domains, |domain| Query::new().domain(domain)),
+            tags: weigh(&self.tags, |tag| Query::new().tag(tag)),
+            content_type: self
+                .content_types
+                .iter()
+                .filter(|(content_type, _)| Query::new().content_type(*content_type).matches(item))
+                .fold(0.0, |sum, (_, weight)| sum + weight),
+        };
+
+        Scored {
+            item: item.clone(),
+            score: factors.total(),
+            factors,
+            minutes,
+        }
+    }
+}
+
+/// Takes items from the front of a ranked queue while they fit in `minutes`,
+/// skipping any that would overrun it or have no known reading time
+pub fn fit(queue: impl IntoIterator<Item = Scored>, minutes: u32) -> Vec<Scored> {
+    let mut left = minutes;
+    queue
+        .into_iter()
+        .filter(|scored| match scored.minutes {
+            Some(minutes) if minutes <= left => {
+                left -= minutes;
+                true
+            }
+            _ => false,
+        })
+        .collect()
+}
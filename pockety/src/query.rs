@@ -0,0 +1,272 @@
+//! Filters over a user's list that go beyond what `/v3/get` supports.
+//!
+//! Pocket only filters on a single tag and a single domain per request and
+//! knows nothing about word counts or when an item was added. A [`Query`]
+//! sends whatever Pocket can handle with the request and applies the rest to
+//! the items that come back:
+//!
+//! | predicate                  | server side (`/v3/get`)             | client side |
+//! |----------------------------|-------------------------------------|-------------|
+//! | `state`                    | `state`, `all` if unset             | yes         |
+//! | `favorite`                 | `favorite`                          | yes         |
+//! | `content_type`             | `contentType`                       | yes         |
+//! | `search`                   | `search`                            | no          |
+//! | `untagged`                 | `tag=_untagged_`                    | yes         |
+//! | `tag` (all of)             | the first tag                       | yes         |
+//! | `any_tag` (any of)         | only if exactly one tag is given    | yes         |
+//! | `domain` (any of)          | only if exactly one domain is given | yes         |
+//! | `min/max_word_count`       | no                                  | yes         |
+//! | `added_after`              | `since` (a superset of the result)  | yes         |
+//! | `added_before`             | no                                  | yes         |
+//! | `updated_since`            | `since`                             | yes         |
+//! | `sort`                     | `sort`                              | no          |
+//!
+//! Client side checks are also applied to predicates Pocket already handled,
+//! so [`Query::matches`] can be used on its own against items you already
+//! have, e.g. a local copy of the list. `search` is the exception: its
+//! matching rules are Pocket's own, so it is only ever applied server side.
+//!
+//! Tag predicates need the items' tags, so queries using them are always
+//! retrieved with `DetailType::Complete`.
+
+use crate::{
+    api::retrieve::RetrieveRequestBody,
+    models::{
+        ContentType, DetailType, ItemHas, ItemStatus, PocketItem, Sort, State, Tag, Timestamp,
+    },
+};
+
+#[derive(Debug, Clone, Default)]
+pub struct Query {
+    /// Defaults to every item that isn't deleted, unlike `/v3/get` on its own
+    pub state: Option<State>,
+    pub favorite: Option<bool>,
+    pub content_type: Option<ContentType>,
+    pub search: Option<String>,
+    /// Only items without any tags
+    pub untagged: bool,
+    /// Items must have every one of these tags
+    pub all_tags: Vec<String>,
+    /// Items must have at least one of these tags
+    pub any_tags: Vec<String>,
+    /// Items must be from one of these domains (or their subdomains)
+    pub domains: Vec<String>,
+    pub min_word_count: Option<u32>,
+    pub max_word_count: Option<u32>,
+    /// Only items added at or after this time
+    pub added_after: Option<Timestamp>,
+    /// Only items added before this time
+    pub added_before: Option<Timestamp>,
+    /// Only items changed at or after this time
+    pub updated_since: Option<Timestamp>,
+    pub sort: Option<Sort>,
+}
+
+impl Query {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn state(mut self, state: State) -> Self {
+        self.state = Some(state);
+        self
+    }
+
+    pub fn favorite(mut self, favorite: bool) -> Self {
+        self.favorite = Some(favorite);
+        self
+    }
+
+    pub fn content_type(mut self, content_type: ContentType) -> Self {
+        self.content_type = Some(content_type);
+        self
+    }
+
+    pub fn search(mut self, search: impl Into<String>) -> Self {
+        self.search = Some(search.into());
+        self
+    }
+
+    pub fn untagged(mut self) -> Self {
+        self.untagged = true;
+        self
+    }
+
+    /// Require the tag. Can be called repeatedly to require several tags.
+    pub fn tag(mut self, tag: impl Into<String>) -> Self {
+        self.all_tags.push(tag.into());
+        self
+    }
+
+    /// Require at least one of the tags
+    pub fn any_tag<T>(mut self, tags: impl IntoIterator<Item = T>) -> Self
+    where
+        T: Into<String>,
+    {
+        self.any_tags.extend(tags.into_iter().map(Into::into));
+        self
+    }
+
+    /// Allow items from the domain. Can be called repeatedly to allow several
+    /// domains.
+    pub fn domain(mut self, domain: impl Into<String>) -> Self {
+        self.domains.push(normalize_domain(&domain.into()));
+        self
+    }
+
+    pub fn min_word_count(mut self, min_word_count: u32) -> Self {
+        self.min_word_count = Some(min_word_count);
+        self
+    }
+
+    pub fn max_word_count(mut self, max_word_count: u32) -> Self {
+        self.max_word_count = Some(max_word_count);
+        self
+    }
+
+    pub fn added_after(mut self, added_after: impl Into<Timestamp>) -> Self {
+        self.added_after = Some(added_after.into());
+        self
+    }
+
+    pub fn added_before(mut self, added_before: impl Into<Timestamp>) -> Self {
+        self.added_before = Some(added_before.into());
+        self
+    }
+
+    pub fn updated_since(mut self, updated_since: impl Into<Timestamp>) -> Self {
+        self.updated_since = Some(updated_since.into());
+        self
+    }
+
+    pub fn sort(mut self, sort: Sort) -> Self {
+        self.sort = Some(sort);
+        self
+    }
+
+    /// Whether the item satisfies every predicate except `search`. Deleted
+    /// items never match.
+    pub fn matches(&self, item: &PocketItem) -> bool {
+        self.matches_state(item)
+            && self
+                .favorite
+                .is_none_or(|favorite| item.is_favorite() == favorite)
+            && self.matches_content_type(item)
+            && self.matches_tags(item)
+            && self.matches_domain(item)
+            && self.matches_word_count(item)
+            && self.matches_time(item)
+    }
+
+    /// Copies the predicates Pocket supports onto a `/v3/get` request
+    pub(crate) fn apply(&self, body: &mut RetrieveRequestBody) {
+        // Pocket only returns unread items without a state, while `matches`
+        // takes any item that isn't deleted
+        body.state = self.state.or(body.state).or(Some(State::All));
+        if self.favorite.is_some() {
+            body.favorite = self.favorite;
+        }
+        if self.content_type.is_some() {
+            body.content_type = self.content_type;
+        }
+        if self.search.is_some() {
+            body.search.clone_from(&self.search);
+        }
+        if self.sort.is_some() {
+            body.sort = self.sort;
+        }
+
+        if self.untagged {
+            body.tag = Some(Tag::Untagged);
+        } else if let Some(tag) = self.all_tags.first() {
+            body.tag = Some(Tag::from(tag.as_str()));
+        } else if let [tag] = self.any_tags.as_slice() {
+            body.tag = Some(Tag::from(tag.as_str()));
+        }
+        if !self.all_tags.is_empty() || !self.any_tags.is_empty() {
+            body.detail_type = Some(DetailType::Complete);
+        }
+
+        if let [domain] = self.domains.as_slice() {
+            body.domain = Some(domain.clone());
+        }
+
+        // anything added after a point in time has also been updated after it
+        let since = self.updated_since.max(self.added_after);
+        if since.is_some() {
+            body.since = since;
+        }
+    }
+
+    fn matches_state(&self, item: &PocketItem) -> bool {
+        match self.state {
+            Some(State::Unread) => item.status == ItemStatus::Normal,
+            Some(State::Archive) => item.status == ItemStatus::Archived,
+            Some(State::All) | None => item.status != ItemStatus::Deleted,
+        }
+    }
+
+    fn matches_content_type(&self, item: &PocketItem) -> bool {
+        match self.content_type {
+            Some(ContentType::Article) => item.is_article(),
+            Some(ContentType::Video) => item.has_video == Some(ItemHas::Is),
+            Some(ContentType::Image) => item.has_image == Some(ItemHas::Is),
+            None => true,
+        }
+    }
+
+    fn matches_tags(&self, item: &PocketItem) -> bool {
+        if self.untagged && !item.tag_names().is_empty() {
+            return false;
+        }
+
+        self.all_tags.iter().all(|tag| item.has_tag(tag))
+            && (self.any_tags.is_empty() || self.any_tags.iter().any(|tag| item.has_tag(tag)))
+    }
+
+    fn matches_domain(&self, item: &PocketItem) -> bool {
+        if self.domains.is_empty() {
+            return true;
+        }
+
+        item.domain().is_some_and(|host| {
+            self.domains.iter().any(|domain| {
+                host == *domain
+                    || host
+                        .strip_suffix(domain.as_str())
+                        .is_some_and(|subdomain| subdomain.ends_with('.'))
+            })
+        })
+    }
+
+    fn matches_word_count(&self, item: &PocketItem) -> bool {
+        if self.min_word_count.is_none() && self.max_word_count.is_none() {
+            return true;
+        }
+
+        item.words().is_some_and(|words| {
+            self.min_word_count.is_none_or(|min| words >= min)
+                && self.max_word_count.is_none_or(|max| words <= max)
+        })
+    }
+
+    fn matches_time(&self, item: &PocketItem) -> bool {
+        let after = |bound: Option<Timestamp>, time: Option<Timestamp>| {
+            bound.is_none_or(|bound| time.is_some_and(|time| time >= bound))
+        };
+
+        after(self.added_after, item.time_added)
+            && after(self.updated_since, item.time_updated)
+            && self
+                .added_before
+                .is_none_or(|bound| item.time_added.is_some_and(|time| time < bound))
+    }
+}
+
+fn normalize_domain(domain: &str) -> String {
+    let domain = domain.trim().to_lowercase();
+    domain
+        .strip_prefix("www.")
+        .map(str::to_string)
+        .unwrap_or(domain)
+}
@@ -0,0 +1,390 @@
+//! Declarative rules that tag, favorite, archive or delete items.
+//!
+//! A [`RuleSet`] is usually loaded from a TOML or JSON file:
+//!
+//! ```toml
+//! [[rules]]
+//! name = "code"
+//! when = { domains = ["github.com", "gitlab.com"] }
+//! then = { tag = ["code"] }
+//!
+//! [[rules]]
+//! name = "old videos"
+//! when = { content_type = "video", older_than_days = 30, state = "unread" }
+//! then = { archive = true }
+//! ```
+//!
+//! Every condition of a rule's `when` must hold for the rule to fire; leaving
+//! a condition out ignores it. Evaluating a rule set against retrieved items
+//! gives the actions to send along with a [`RuleMatch`] per rule and item
+//! explaining why they are sent.
+//!
+//! Rules are evaluated against the items as they were retrieved, not as
+//! earlier rules would leave them. Actions that wouldn't change an item, like
+//! adding a tag it already has, are left out, and an item that a rule deletes
+//! gets no other actions.
+
+use std::{fmt, fs, path::Path};
+
+use chrono::{Duration, Utc};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    api::modify::PocketAction,
+    models::{ContentType, ItemStatus, PocketItem, State, Timestamp},
+    query::Query,
+    Error,
+};
+
+/// A regular expression in a rule, written as a string
+#[derive(Debug, Clone)]
+pub struct Pattern(pub Regex);
+
+impl Pattern {
+    pub fn is_match(&self, text: &str) -> bool {
+        self.0.is_match(text)
+    }
+}
+
+impl<'de> Deserialize<'de> for Pattern {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let pattern = String::deserialize(deserializer)?;
+        Regex::new(&pattern)
+            .map(Pattern)
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+impl Serialize for Pattern {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        self.0.as_str().serialize(serializer)
+    }
+}
+
+/// When a rule fires. Conditions that are left out always hold.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct Conditions {
+    /// The item is from one of these domains (or their subdomains)
+    pub domains: Vec<String>,
+    /// The item's url matches this regular expression
+    pub url: Option<Pattern>,
+    /// The item's title matches this regular expression
+    pub title: Option<Pattern>,
+    pub content_type: Option<ContentType>,
+    pub min_word_count: Option<u32>,
+    pub max_word_count: Option<u32>,
+    /// The item was added more than this many days ago
+    pub older_than_days: Option<u32>,
+    /// The item was added at most this many days ago
+    pub newer_than_days: Option<u32>,
+    /// The item has every one of these tags
+    pub tags: Vec<String>,
+    /// The item has at least one of these tags
+    pub any_tags: Vec<String>,
+    /// The item has none of these tags
+    pub without_tags: Vec<String>,
+    /// The item has no tags at all
+    pub untagged: bool,
+    pub favorite: Option<bool>,
+    /// Defaults to both unread and archived items
+    pub state: Option<State>,
+}
+
+impl Conditions {
+    fn matches(&self, item: &PocketItem, now: Timestamp) -> bool {
+        self.query(now).matches(item)
+            && self
+                .url
+                .as_ref()
+                .is_none_or(|pattern| item.url().is_some_and(|url| pattern.is_match(url)))
+            && self
+                .title
+                .as_ref()
+                .is_none_or(|pattern| item.title().is_some_and(|title| pattern.is_match(title)))
+            && !self.without_tags.iter().any(|tag| item.has_tag(tag))
+    }
+
+    /// The conditions a [`Query`] can check
+    fn query(&self, now: Timestamp) -> Query {
+        let days_ago = |days: u32| Timestamp(now.0 - Duration::days(days.into()).num_seconds());
+
+        let mut query = Query {
+            state: self.state,
+            favorite: self.favorite,
+            content_type: self.content_type,
+            untagged: self.untagged,
+            all_tags: self.tags.clone(),
+            any_tags: self.any_tags.clone(),
+            min_word_count: self.min_word_count,
+            max_word_count: self.max_word_count,
+            added_before: self.older_than_days.map(days_ago),
+            added_after: self.newer_than_days.map(days_ago),
+            ..Default::default()
+        };
+        for domain in &self.domains {
+            query = query.domain(domain.as_str());
+        }
+        query
+    }
+
+    /// A description of each condition, for [`RuleMatch::conditions`]
+    fn describe(&self) -> Vec<String> {
+        let list = |values: &[String]| values.join(", ");
+        let mut conditions = Vec::new();
+
+        if !self.domains.is_empty() {
+            conditions.push(format!("is from {}", list(&self.domains)));
+        }
+        if let Some(pattern) = &self.url {
+            conditions.push(format!("has a url matching `{}`", pattern.0.as_str()));
+        }
+        if let Some(pattern) = &self.title {
+            conditions.push(format!("has a title matching `{}`", pattern.0.as_str()));
+        }
+        if let Some(content_type) = self.content_type {
+            conditions.push(
+                match content_type {
+                    ContentType::Article => "is an article",
+                    ContentType::Video => "is a video",
+                    ContentType::Image => "is an image",
+                }
+                .to_string(),
+            );
+        }
+        if let Some(min) = self.min_word_count {
+            conditions.push(format!("has at least {min} words"));
+        }
+        if let Some(max) = self.max_word_count {
+            conditions.push(format!("has at most {max} words"));
+        }
+        if let Some(days) = self.older_than_days {
+            conditions.push(format!("was added more than {days} days ago"));
+        }
+        if let Some(days) = self.newer_than_days {
+            conditions.push(format!("was added in the last {days} days"));
+        }
+        if !self.tags.is_empty() {
+            conditions.push(format!("is tagged {}", list(&self.tags)));
+        }
+        if !self.any_tags.is_empty() {
+            conditions.push(format!("is tagged one of {}", list(&self.any_tags)));
+        }
+        if !self.without_tags.is_empty() {
+            conditions.push(format!("isn't tagged {}", list(&self.without_tags)));
+        }
+        if self.untagged {
+            conditions.push("has no tags".to_string());
+        }
+        if let Some(favorite) = self.favorite {
+            conditions.push(
+                if favorite {
+                    "is a favorite"
+                } else {
+                    "isn't a favorite"
+                }
+                .to_string(),
+            );
+        }
+        match self.state {
+            Some(State::Unread) => conditions.push("is unread".to_string()),
+            Some(State::Archive) => conditions.push("is archived".to_string()),
+            Some(State::All) | None => {}
+        }
+        conditions
+    }
+}
+
+/// What a rule does to the items it fires on
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+#[serde(default, deny_unknown_fields)]
+pub struct RuleActions {
+    /// Tags to add
+    pub tag: Vec<String>,
+    pub favorite: bool,
+    pub archive: bool,
+    pub delete: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct Rule {
+    pub name: String,
+    #[serde(default)]
+    pub when: Conditions,
+    pub then: RuleActions,
+    /// Don't evaluate later rules for the items this rule fires on
+    #[serde(default)]
+    pub stop: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(deny_unknown_fields)]
+pub struct RuleSet {
+    #[serde(default)]
+    pub rules: Vec<Rule>,
+}
+
+/// A rule that fired on an item, and the actions it produced
+#[derive(Debug, Clone)]
+pub struct RuleMatch {
+    pub rule: String,
+    pub item: PocketItem,
+    /// The rule's conditions, described
+    pub conditions: Vec<String>,
+    /// The actions the rule produced, without the ones that wouldn't change
+    /// the item. Empty if the item is already as the rule would leave it.
+    pub actions: Vec<PocketAction>,
+}
+
+impl fmt::Display for RuleMatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let item = self
+            .item
+            .title()
+            .or(self.item.url())
+            .unwrap_or(&self.item.item_id.0);
+        write!(f, "rule `{}` fired on \"{item}\"", self.rule)?;
+        if !self.conditions.is_empty() {
+            write!(f, " because it {}", self.conditions.join(" and "))?;
+        }
+        Ok(())
+    }
+}
+
+/// The result of evaluating a [`RuleSet`]
+#[derive(Debug, Clone, Default)]
+pub struct Evaluation {
+    pub matches: Vec<RuleMatch>,
+}
+
+impl Evaluation {
+    /// Every action to send, in the order the rules fired
+    pub fn actions(&self) -> Vec<PocketAction> {
+        self.matches
+            .iter()
+            .flat_map(|rule_match| rule_match.actions.iter().cloned())
+            .collect()
+    }
+}
+
+impl RuleSet {
+    pub fn from_toml(rules: &str) -> Result<Self, Error> {
+        toml::from_str(rules).map_err(|e| Error::Parse(e.to_string()))
+    }
+
+    pub fn from_json(rules: &str) -> Result<Self, Error> {
+        Ok(serde_json::from_str(rules)?)
+    }
+
+    /// Loads rules from a `.toml` file, or a JSON file otherwise
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let path = path.as_ref();
+        let rules = fs::read_to_string(path)?;
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("toml") => Self::from_toml(&rules),
+            _ => Self::from_json(&rules),
+        }
+    }
+
+    pub fn evaluate<'a>(&self, items: impl IntoIterator<Item = &'a PocketItem>) -> Evaluation {
+        self.evaluate_at(items, Utc::now())
+    }
+
+    /// Evaluates the rules with ages relative to `now` rather than the
+    /// current time
+    pub fn evaluate_at<'a>(
+        &self,
+        items: impl IntoIterator<Item = &'a PocketItem>,
+        now: impl Into<Timestamp>,
+    ) -> Evaluation {
+        let now = now.into();
+        let mut matches = Vec::new();
+
+        for item in items {
+            if item.status == ItemStatus::Deleted {
+                continue;
+            }
+
+            let first = matches.len();
+            let mut planned = Planned::default();
+            for rule in &self.rules {
+                if planned.delete {
+                    break;
+                }
+                if !rule.when.matches(item, now) {
+                    continue;
+                }
+
+                matches.push(RuleMatch {
+                    rule: rule.name.clone(),
+                    item: item.clone(),
+                    conditions: rule.when.describe(),
+                    actions: planned.actions(item, &rule.then),
+                });
+                if rule.stop {
+                    break;
+                }
+            }
+
+            // deleting the item makes the actions of earlier rules moot
+            if planned.delete {
+                for rule_match in &mut matches[first..] {
+                    rule_match
+                        .actions
+                        .retain(|action| matches!(action, PocketAction::Delete(_)));
+                }
+            }
+        }
+
+        Evaluation { matches }
+    }
+}
+
+/// What earlier rules already do to an item
+#[derive(Debug, Default)]
+struct Planned {
+    tags: Vec<String>,
+    favorite: bool,
+    archive: bool,
+    delete: bool,
+}
+
+impl Planned {
+    fn actions(&mut self, item: &PocketItem, then: &RuleActions) -> Vec<PocketAction> {
+        let item_id = &item.item_id;
+
+        if then.delete {
+            self.delete = true;
+            return vec![PocketAction::delete(item_id)];
+        }
+
+        let mut actions = Vec::new();
+        let tags: Vec<String> = then
+            .tag
+            .iter()
+            .filter(|tag| !item.has_tag(tag) && !self.tags.contains(tag))
+            .cloned()
+            .collect();
+        if !tags.is_empty() {
+            self.tags.extend(tags.iter().cloned());
+            actions.push(PocketAction::tags_add(item_id, tags));
+        }
+        if then.favorite && !item.is_favorite() && !self.favorite {
+            self.favorite = true;
+            actions.push(PocketAction::favorite(item_id));
+        }
+        if then.archive && item.status != ItemStatus::Archived && !self.archive {
+            self.archive = true;
+            actions.push(PocketAction::archive(item_id));
+        }
+        actions
+    }
+}
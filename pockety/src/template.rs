@@ -0,0 +1,343 @@
+//! A small subset of [Mustache](https://mustache.github.io/mustache.5.html)
+//! for rendering serializable values, used for [`digest`](crate::digest)s.
+//!
+//! - `{{name}}` inserts a value, escaped in html templates, and `{{{name}}}`
+//!   inserts it unescaped. Names can be dotted (`{{item.title}}`) and `{{.}}`
+//!   is the current value.
+//! - `{{#name}}...{{/name}}` renders its content once per element of a
+//!   list, once for any other truthy value and not at all for `null`,
+//!   `false`, `0`, `""` or `[]`
+//! - `{{^name}}...{{/name}}` renders its content only for falsy values
+//!
+//! Section tags alone on a line don't leave the line behind.
+//!
+//! ```
+//! use pockety::template::Template;
+//! use serde_json::json;
+//!
+//! let template = Template::html("<ul>{{#items}}<li>{{title}}</li>{{/items}}</ul>").unwrap();
+//! let context = json!({ "items": [{ "title": "Fish & chips" }] });
+//! assert_eq!(template.render(&context), "<ul><li>Fish &amp; chips</li></ul>");
+//! ```
+
+use serde_json::Value;
+
+use crate::{export::escape_html, Error};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Template {
+    nodes: Vec<Node>,
+    escape: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Node {
+    Text(String),
+    Value {
+        name: String,
+        escape: bool,
+    },
+    Section {
+        name: String,
+        inverted: bool,
+        nodes: Vec<Node>,
+    },
+}
+
+impl Template {
+    /// A template whose `{{name}}` values are html escaped
+    pub fn html(source: &str) -> Result<Self, Error> {
+        Ok(Self {
+            nodes: parse(source)?,
+            escape: true,
+        })
+    }
+
+    /// A template whose values are inserted as they are
+    pub fn text(source: &str) -> Result<Self, Error> {
+        Ok(Self {
+            nodes: parse(source)?,
+            escape: false,
+        })
+    }
+
+    pub fn render(&self, context: &Value) -> String {
+        let mut output = String::new();
+        render(&self.nodes, &mut vec![context], self.escape, &mut output);
+        output
+    }
+}
+
+fn parse(source: &str) -> Result<Vec<Node>, Error> {
+    // sections being parsed, innermost last, with the nodes before them
+    let mut open: Vec<(String, bool, Vec<Node>)> = Vec::new();
+    let mut nodes = Vec::new();
+    let mut rest = source;
+    // whether `rest` starts a line, so text before a tag is all of its line
+    let mut line_start = true;
+
+    while let Some(start) = rest.find("{{") {
+        let mut text = &rest[..start];
+        let after = &rest[start + 2..];
+        let (tag, mut after) = if let Some(inner) = after.strip_prefix('{') {
+            let end = inner
+                .find("}}}")
+                .ok_or_else(|| unclosed(source, &rest[start..]))?;
+            (&after[..end + 1], &inner[end + 3..])
+        } else {
+            let end = after
+                .find("}}")
+                .ok_or_else(|| unclosed(source, &rest[start..]))?;
+            (&after[..end], &after[end + 2..])
+        };
+
+        let mut stripped = false;
+        if tag.starts_with(['#', '^', '/']) && (line_start || text.contains('\n')) {
+            if let Some((indent, line)) = standalone(text, after) {
+                text = &text[..text.len() - indent];
+                after = &after[line..];
+                stripped = true;
+            }
+        }
+        line_start = stripped;
+        if !text.is_empty() {
+            nodes.push(Node::Text(text.to_string()));
+        }
+        rest = after;
+
+        let tag = tag.trim();
+        if let Some(name) = tag.strip_prefix('{') {
+            nodes.push(Node::Value {
+                name: name.trim().to_string(),
+                escape: false,
+            });
+        } else if let Some(name) = tag.strip_prefix('&') {
+            nodes.push(Node::Value {
+                name: name.trim().to_string(),
+                escape: false,
+            });
+        } else if let Some(name) = tag.strip_prefix('#') {
+            open.push((name.trim().to_string(), false, std::mem::take(&mut nodes)));
+        } else if let Some(name) = tag.strip_prefix('^') {
+            open.push((name.trim().to_string(), true, std::mem::take(&mut nodes)));
+        } else if let Some(name) = tag.strip_prefix('/') {
+            let name = name.trim();
+            match open.pop() {
+                Some((section, inverted, before)) if section == name => {
+                    let inner = std::mem::replace(&mut nodes, before);
+                    nodes.push(Node::Section {
+                        name: section,
+                        inverted,
+                        nodes: inner,
+                    });
+                }
+                Some((section, ..)) => {
+                    return Err(Error::Parse(format!(
+                        "template closes `{name}` but `{section}` is open"
+                    )))
+                }
+                None => {
+                    return Err(Error::Parse(format!(
+                        "template closes `{name}`, which isn't open"
+                    )))
+                }
+            }
+        } else if !tag.starts_with('!') {
+            nodes.push(Node::Value {
+                name: tag.to_string(),
+                escape: true,
+            });
+        }
+    }
+
+    if let Some((section, ..)) = open.pop() {
+        return Err(Error::Parse(format!(
+            "template section `{section}` is never closed"
+        )));
+    }
+    if !rest.is_empty() {
+        nodes.push(Node::Text(rest.to_string()));
+    }
+    Ok(nodes)
+}
+
+fn unclosed(source: &str, rest: &str) -> Error {
+    Error::Parse(format!(
+        "unclosed template tag at byte {}",
+        source.len() - rest.len()
+    ))
+}
+
+/// If a tag is alone on its line, how much whitespace to remove before it
+/// and after it, including the line break
+fn standalone(before: &str, after: &str) -> Option<(usize, usize)> {
+    let indent = before.len() - before.rfind('\n').map_or(0, |newline| newline + 1);
+    let line_end = after.find('\n').map_or(after.len(), |newline| newline + 1);
+
+    let is_blank = |text: &str| text.chars().all(char::is_whitespace);
+    (is_blank(&before[before.len() - indent..]) && is_blank(&after[..line_end]))
+        .then_some((indent, line_end))
+}
+
+fn render(nodes: &[Node], stack: &mut Vec<&Value>, escape: bool, output: &mut String) {
+    for node in nodes {
+        match node {
+            Node::Text(text) => output.push_str(text),
+            Node::Value {
+                name,
+                escape: escape_value,
+            } => {
+                let text = to_text(lookup(stack, name));
+                if escape && *escape_value {
+                    output.push_str(&escape_html(&text));
+                } else {
+                    output.push_str(&text);
+                }
+            }
+            Node::Section {
+                name,
+                inverted,
+                nodes,
+            } => {
+                let value = lookup(stack, name);
+                if *inverted {
+                    if !is_truthy(value) {
+                        render(nodes, stack, escape, output);
+                    }
+                    continue;
+                }
+                match value {
+                    Value::Array(elements) => {
+                        for element in elements {
+                            stack.push(element);
+                            render(nodes, stack, escape, output);
+                            stack.pop();
+                        }
+                    }
+                    value if is_truthy(value) => {
+                        stack.push(value);
+                        render(nodes, stack, escape, output);
+                        stack.pop();
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+}
+
+/// Finds a name in the innermost value that has it
+fn lookup<'a>(stack: &[&'a Value], name: &str) -> &'a Value {
+    if name == "." {
+        return stack.last().copied().unwrap_or(&Value::Null);
+    }
+
+    let mut parts = name.split('.');
+    let first = parts.next().unwrap_or_default();
+    let Some(mut value) = stack
+        .iter()
+        .rev()
+        .find_map(|context| context.as_object()?.get(first))
+    else {
+        return &Value::Null;
+    };
+    for part in parts {
+        value = value.get(part).unwrap_or(&Value::Null);
+    }
+    value
+}
+
+fn is_truthy(value: &Value) -> bool {
+    match value {
+        Value::Null => false,
+        Value::Bool(value) => *value,
+        Value::Number(number) => number.as_f64() != Some(0.0),
+        Value::String(text) => !text.is_empty(),
+        Value::Array(elements) => !elements.is_empty(),
+        Value::Object(_) => true,
+    }
+}
+
+fn to_text(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(text) => text.clone(),
+        value => value.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn escapes_values_in_html_templates_only() {
+        let context = json!({ "title": "<b>Fish & \"chips\"</b>" });
+
+        assert_eq!(
+            Template::html("{{title}}").unwrap().render(&context),
+            "&lt;b&gt;Fish &amp; &quot;chips&quot;&lt;/b&gt;"
+        );
+        assert_eq!(
+            Template::text("{{title}}").unwrap().render(&context),
+            "<b>Fish & \"chips\"</b>"
+        );
+    }
+
+    #[test]
+    fn inserts_triple_braces_and_ampersands_unescaped() {
+        let template = Template::html("{{{title}}} {{& title}}").unwrap();
+        assert_eq!(template.render(&json!({ "title": "a & b" })), "a & b a & b");
+    }
+
+    #[test]
+    fn renders_sections_per_element_and_inverted_sections_when_falsy() {
+        let template =
+            Template::text("{{#items}}[{{.}}]{{/items}}{{^items}}none{{/items}}").unwrap();
+
+        assert_eq!(template.render(&json!({ "items": [1, 2] })), "[1][2]");
+        for empty in [json!([]), json!(null), json!(false), json!(0), json!("")] {
+            assert_eq!(template.render(&json!({ "items": empty })), "none");
+        }
+    }
+
+    #[test]
+    fn looks_up_dotted_names_through_enclosing_sections() {
+        let template = Template::text("{{#items}}{{title}} in {{list.name}};{{/items}}").unwrap();
+        let context = json!({
+            "list": { "name": "Reading" },
+            "items": [{ "title": "A" }, { "title": "B" }],
+        });
+
+        assert_eq!(template.render(&context), "A in Reading;B in Reading;");
+    }
+
+    #[test]
+    fn strips_lines_holding_only_a_section_tag() {
+        let template =
+            Template::text("<ul>\n  {{#items}}\n  <li>{{.}}</li>\n  {{/items}}\n</ul>\n").unwrap();
+
+        assert_eq!(
+            template.render(&json!({ "items": ["a", "b"] })),
+            "<ul>\n  <li>a</li>\n  <li>b</li>\n</ul>\n"
+        );
+    }
+
+    #[test]
+    fn keeps_section_tags_that_share_their_line() {
+        let template = Template::text("a {{#flag}}b{{/flag}}\nc").unwrap();
+        assert_eq!(template.render(&json!({ "flag": true })), "a b\nc");
+    }
+
+    #[test]
+    fn rejects_mismatched_and_unclosed_sections() {
+        for source in ["{{#a}}{{/b}}", "{{/a}}", "{{#a}}", "{{title"] {
+            assert!(
+                matches!(Template::text(source), Err(Error::Parse(_))),
+                "{source}"
+            );
+        }
+    }
+}
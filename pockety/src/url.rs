@@ -0,0 +1,193 @@
+//! Normalization of urls before they are saved, so that the same page isn't
+//! saved under several urls and tracking parameters don't end up in a list.
+
+use reqwest::Url;
+
+use crate::{Error, UrlError};
+
+/// Query parameters that only exist to track where a click came from
+pub const TRACKING_PARAMS: &[&str] = &[
+    "fbclid", "gclid", "dclid", "msclkid", "yclid", "igshid", "mc_cid", "mc_eid", "_hsenc", "_hsmi",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrailingSlash {
+    /// Leave the path as it is
+    Keep,
+    /// `https://example.com/a/` becomes `https://example.com/a`
+    Remove,
+    /// `https://example.com/a` becomes `https://example.com/a/`
+    Add,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NormalizeOptions {
+    /// Scheme to use for urls without one, e.g. `example.com/a`
+    pub default_scheme: String,
+    /// Remove `utm_*` parameters and those in [`TRACKING_PARAMS`]
+    pub strip_tracking_params: bool,
+    /// Remove the `#fragment`
+    pub strip_fragment: bool,
+    pub trailing_slash: TrailingSlash,
+}
+
+impl Default for NormalizeOptions {
+    fn default() -> Self {
+        Self {
+            default_scheme: "https".to_string(),
+            strip_tracking_params: true,
+            strip_fragment: false,
+            trailing_slash: TrailingSlash::Keep,
+        }
+    }
+}
+
+/// Validates and normalizes a url to save.
+///
+/// Only `http` and `https` urls with a host are accepted, and urls without a
+/// scheme get `default_scheme`. The scheme and host are always lowercased,
+/// and the default port is dropped; everything else depends on `options`.
+///
+/// ```
+/// use pockety::url::{normalize_url, NormalizeOptions};
+///
+/// let url = normalize_url(
+///     "Example.COM/post?id=1&utm_source=feed&fbclid=abc",
+///     &NormalizeOptions::default(),
+/// )
+/// .unwrap();
+/// assert_eq!(url, "https://example.com/post?id=1");
+/// ```
+pub fn normalize_url(url: &str, options: &NormalizeOptions) -> Result<String, Error> {
+    let url = url.trim();
+    if url.is_empty() {
+        return Err(Error::Url(UrlError::Empty));
+    }
+
+    let url = match scheme(url) {
+        Some(scheme)
+            if !scheme.eq_ignore_ascii_case("http") && !scheme.eq_ignore_ascii_case("https") =>
+        {
+            return Err(Error::Url(UrlError::UnsupportedScheme(
+                scheme.to_lowercase(),
+            )));
+        }
+        Some(_) => url.to_string(),
+        None => format!("{}://{url}", options.default_scheme),
+    };
+    let mut url = Url::parse(&url).map_err(|e| Error::Url(UrlError::Invalid(e.to_string())))?;
+
+    if !matches!(url.scheme(), "http" | "https") {
+        return Err(Error::Url(UrlError::UnsupportedScheme(
+            url.scheme().to_string(),
+        )));
+    }
+    match url.host_str() {
+        Some(host) if !host.is_empty() => {}
+        _ => return Err(Error::Url(UrlError::MissingHost)),
+    }
+
+    if options.strip_tracking_params {
+        // only rewrite the query if needed, as rewriting re-encodes it
+        if url.query_pairs().any(|(key, _)| is_tracking_param(&key)) {
+            let params: Vec<(String, String)> = url
+                .query_pairs()
+                .filter(|(key, _)| !is_tracking_param(key))
+                .map(|(key, value)| (key.into_owned(), value.into_owned()))
+                .collect();
+            if params.is_empty() {
+                url.set_query(None);
+            } else {
+                url.query_pairs_mut().clear().extend_pairs(params);
+            }
+        }
+    }
+
+    if options.strip_fragment {
+        url.set_fragment(None);
+    }
+
+    match options.trailing_slash {
+        TrailingSlash::Keep => {}
+        TrailingSlash::Remove => {
+            if url.path() != "/" && url.path().ends_with('/') {
+                let path = url.path().trim_end_matches('/').to_string();
+                url.set_path(&path);
+            }
+        }
+        TrailingSlash::Add => {
+            if !url.path().ends_with('/') {
+                let path = format!("{}/", url.path());
+                url.set_path(&path);
+            }
+        }
+    }
+
+    let mut normalized = String::from(url.clone());
+    // the root path can't be removed from a `Url`, only from its string
+    if options.trailing_slash == TrailingSlash::Remove
+        && url.path() == "/"
+        && url.query().is_none()
+        && url.fragment().is_none()
+    {
+        normalized.pop();
+    }
+
+    Ok(normalized)
+}
+
+/// The scheme the url starts with, if any. `example.com:8080/a` has a port
+/// rather than a scheme.
+fn scheme(url: &str) -> Option<&str> {
+    let (scheme, rest) = url.split_once(':')?;
+    let mut chars = scheme.chars();
+    let is_scheme = chars.next()?.is_ascii_alphabetic()
+        && chars.all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'));
+
+    let port = rest.split(['/', '?', '#']).next().unwrap_or_default();
+    let is_port = !port.is_empty() && port.chars().all(|c| c.is_ascii_digit());
+
+    (is_scheme && !is_port).then_some(scheme)
+}
+
+fn is_tracking_param(key: &str) -> bool {
+    let key = key.to_lowercase();
+    key.starts_with("utm_") || TRACKING_PARAMS.contains(&key.as_str())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn normalize(url: &str) -> Result<String, Error> {
+        normalize_url(url, &NormalizeOptions::default())
+    }
+
+    #[test]
+    fn adds_the_default_scheme_when_the_query_contains_a_url() {
+        assert_eq!(
+            normalize("example.com/r?to=https://x.com").unwrap(),
+            "https://example.com/r?to=https://x.com"
+        );
+    }
+
+    #[test]
+    fn adds_the_default_scheme_before_a_port() {
+        assert_eq!(
+            normalize("example.com:8080/a").unwrap(),
+            "https://example.com:8080/a"
+        );
+    }
+
+    #[test]
+    fn rejects_schemes_other_than_http() {
+        assert!(matches!(
+            normalize("mailto:a@b.c"),
+            Err(Error::Url(UrlError::UnsupportedScheme(scheme))) if scheme == "mailto"
+        ));
+        assert!(matches!(
+            normalize("FTP://example.com/a"),
+            Err(Error::Url(UrlError::UnsupportedScheme(scheme))) if scheme == "ftp"
+        ));
+    }
+}
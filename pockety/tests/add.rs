@@ -0,0 +1,80 @@
+mod common;
+
+use pockety::{
+    api::add::{AddRequestBody, AddResponse},
+    models::{ItemHas, ItemId, Tags, Timestamp},
+};
+
+const ADD_RESPONSE: &str = include_str!("fixtures/add_response.json");
+const ADD_RESPONSE_UNRESOLVED: &str = include_str!("fixtures/add_response_unresolved.json");
+
+#[test]
+fn deserializes_documented_add_response() {
+    let response: AddResponse = serde_json::from_str(ADD_RESPONSE).unwrap();
+    let item = response.item;
+
+    assert_eq!(response.status, 1);
+    assert_eq!(item.item_id, ItemId::from("229279689"));
+    assert_eq!(item.resolved_id, Some(ItemId::from("229279689")));
+    assert_eq!(item.content_length, Some(44440));
+    assert_eq!(item.word_count, Some(3197));
+    assert_eq!(item.date_resolved, Some(Timestamp(1349305496)));
+    assert_eq!(item.date_published, None);
+    assert_eq!(item.has_image, Some(ItemHas::Yes));
+    assert_eq!(item.is_article, Some(true));
+    assert_eq!(item.is_index, Some(false));
+    assert_eq!(item.authors.unwrap()[0].name, "Shane Ryan");
+    assert_eq!(item.images.unwrap().len(), 1);
+    assert_eq!(item.videos.unwrap()[0].vid, "Er34PbFkVGk");
+    assert_eq!(item.lang.as_deref(), Some("en"));
+    assert!(item.extra.is_empty());
+}
+
+#[test]
+fn deserializes_unresolved_add_response() {
+    let response: AddResponse = serde_json::from_str(ADD_RESPONSE_UNRESOLVED).unwrap();
+    let item = response.item;
+
+    assert_eq!(item.item_id, ItemId::from("3796491221"));
+    assert_eq!(item.date_resolved, None);
+    assert_eq!(item.word_count, Some(0));
+    assert_eq!(item.is_article, Some(false));
+    assert_eq!(item.has_image, None);
+    assert_eq!(item.authors, Some(vec![]));
+}
+
+#[test]
+fn serializes_tags_comma_separated() {
+    let body = AddRequestBody {
+        url: "https://example.com".to_string(),
+        tags: Some(Tags::from(["rust", "async"])),
+        ..Default::default()
+    };
+
+    let body = serde_json::to_value(body).unwrap();
+    assert_eq!(body["tags"], "rust,async");
+    assert!(body.get("title").is_none());
+}
+
+#[tokio::test]
+async fn posts_to_add_endpoint() {
+    let (pockety, server) = common::mock_pocket(ADD_RESPONSE, || {}).await;
+
+    let response = pockety
+        .add()
+        .access_token("access_token".to_string())
+        .url(
+            "http://www.grantland.com/blog/the-triangle/post/_/id/38347/ryder-cup-preview"
+                .to_string(),
+        )
+        .send()
+        .await
+        .unwrap();
+
+    let request = server.await.unwrap();
+    assert!(request.starts_with("POST /add "));
+    assert!(request.ends_with(
+        r#""url":"http://www.grantland.com/blog/the-triangle/post/_/id/38347/ryder-cup-preview"}"#
+    ));
+    assert_eq!(response.data.item.item_id, ItemId::from("229279689"));
+}
@@ -0,0 +1,62 @@
+use pockety::Pockety;
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpListener,
+    task::JoinHandle,
+};
+
+/// A client pointed at a server that answers a single request with `body`,
+/// calling `during` once the whole request has arrived. The server returns
+/// the request it received.
+pub async fn mock_pocket(
+    body: impl Into<String>,
+    during: impl FnOnce() + Send + 'static,
+) -> (Pockety, JoinHandle<String>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let mut pockety = Pockety::new("consumer_key", "https://example.com/redirect").unwrap();
+    pockety.base_url = format!("http://{}", listener.local_addr().unwrap());
+
+    let body = body.into();
+    let server = tokio::spawn(async move {
+        let (mut stream, _) = listener.accept().await.unwrap();
+        let request = read_request(&mut stream).await;
+
+        during();
+
+        let response = format!(
+            "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{body}",
+            body.len()
+        );
+        stream.write_all(response.as_bytes()).await.unwrap();
+        request
+    });
+
+    (pockety, server)
+}
+
+/// Reads the headers and then `content-length` bytes of body
+async fn read_request(stream: &mut (impl AsyncReadExt + Unpin)) -> String {
+    let mut request = Vec::new();
+    let mut buffer = [0; 4096];
+    loop {
+        let text = String::from_utf8_lossy(&request);
+        if let Some(headers_end) = text.find("\r\n\r\n") {
+            let content_length = text[..headers_end]
+                .lines()
+                .filter_map(|line| line.split_once(':'))
+                .find(|(name, _)| name.trim().eq_ignore_ascii_case("content-length"))
+                .and_then(|(_, value)| value.trim().parse::<usize>().ok())
+                .unwrap_or_default();
+            if request.len() >= headers_end + 4 + content_length {
+                return String::from_utf8(request).unwrap();
+            }
+        }
+
+        let read = stream.read(&mut buffer).await.unwrap();
+        assert!(
+            read > 0,
+            "connection closed before the request was complete"
+        );
+        request.extend_from_slice(&buffer[..read]);
+    }
+}
@@ -0,0 +1,50 @@
+mod common;
+
+use std::fs;
+
+use pockety::api::{modify::PocketAction, queue::ActionQueue};
+
+const SEND_RESPONSE: &str = r#"{"status":1,"action_results":[true],"action_errors":[null]}"#;
+
+fn queue(name: &str) -> ActionQueue {
+    let path =
+        std::env::temp_dir().join(format!("pockety-queue-{}-{name}.jsonl", std::process::id()));
+    let _ = fs::remove_file(&path);
+    ActionQueue::open(path).unwrap()
+}
+
+#[tokio::test]
+async fn keeps_actions_pushed_during_a_flush() {
+    let queue = queue("flush");
+    queue.push(&PocketAction::archive("1")).unwrap();
+
+    let worker = queue.clone();
+    let (pockety, server) = common::mock_pocket(SEND_RESPONSE, move || {
+        worker.push(&PocketAction::favorite("2")).unwrap();
+    })
+    .await;
+    let outcome = queue.flush(&pockety, "access_token").await.unwrap();
+    server.await.unwrap();
+
+    assert_eq!(outcome.data.len(), 1);
+    assert_eq!(queue.pending().unwrap(), vec![PocketAction::favorite("2")]);
+    fs::remove_file(queue.path()).unwrap();
+}
+
+#[tokio::test]
+async fn keeps_actions_pushed_after_clearing_during_a_flush() {
+    let queue = queue("clear");
+    queue.push(&PocketAction::archive("1")).unwrap();
+
+    let worker = queue.clone();
+    let (pockety, server) = common::mock_pocket(SEND_RESPONSE, move || {
+        worker.clear().unwrap();
+        worker.push(&PocketAction::favorite("2")).unwrap();
+    })
+    .await;
+    queue.flush(&pockety, "access_token").await.unwrap();
+    server.await.unwrap();
+
+    assert_eq!(queue.pending().unwrap(), vec![PocketAction::favorite("2")]);
+    fs::remove_file(queue.path()).unwrap();
+}